@@ -0,0 +1,96 @@
+use std::fs::File;
+use std::io::BufReader;
+
+use rust_nn_framewrk::neural_sim::analysis::{SpikeTrains, read_spikes_csv, van_rossum_distance, victor_purpura_distance};
+use rust_nn_framewrk::neural_sim::error::Error;
+
+const USAGE: &str = "usage: spike_report <spikes.csv> [--start STEP] [--end STEP] [--bin STEPS] [--lag STEPS] [--tau STEPS] [--cost COST]";
+
+struct Options {
+    path: String,
+    start: Option<u32>,
+    end: Option<u32>,
+    bin: u32,
+    lag: u32,
+    tau: f32,
+    cost: f32,
+}
+
+fn parse_options() -> Option<Options> {
+    let mut args = std::env::args().skip(1);
+    let mut options = Options { path: String::new(), start: None, end: None, bin: 10, lag: 5, tau: 10., cost: 0.1 };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--start" => options.start = Some(args.next()?.parse().ok()?),
+            "--end" => options.end = Some(args.next()?.parse().ok()?),
+            "--bin" => options.bin = args.next()?.parse().ok()?,
+            "--lag" => options.lag = args.next()?.parse().ok()?,
+            "--tau" => options.tau = args.next()?.parse().ok()?,
+            "--cost" => options.cost = args.next()?.parse().ok()?,
+            path if options.path.is_empty() && !path.starts_with("--") => options.path = path.to_string(),
+            _ => return None,
+        }
+    }
+    (!options.path.is_empty()).then_some(options)
+}
+
+fn format_optional(value: Option<f32>) -> String {
+    value.map_or("-".to_string(), |value| format!("{value:.3}"))
+}
+
+fn main() -> Result<(), Error> {
+    let Some(options) = parse_options() else {
+        eprintln!("{USAGE}");
+        std::process::exit(2);
+    };
+    let spikes = read_spikes_csv(BufReader::new(File::open(&options.path)?))?;
+    let start = options.start.unwrap_or(0);
+    let end = options.end.unwrap_or_else(|| spikes.iter().map(|(_, time_step)| time_step.saturating_add(1)).max().unwrap_or(0));
+    let trains = SpikeTrains::new(&spikes, start..end);
+
+    println!("spikes: {} of {} neurons in steps {start}..{end}", trains.spike_count(), trains.neurons().count());
+    println!("population rate: {:.4} spikes/step/neuron", trains.population_rate());
+    println!("synchrony (bin {}): {}", options.bin, format_optional(trains.synchrony(options.bin)));
+
+    println!("\n{:>8} {:>7} {:>8} {:>7} {:>7}", "neuron", "spikes", "rate", "cv", "fano");
+    for id in trains.neurons() {
+        println!(
+            "{id:>8} {:>7} {:>8.4} {:>7} {:>7}",
+            trains.train(id).len(),
+            trains.rate(id),
+            format_optional(trains.cv(id)),
+            format_optional(trains.fano_factor(id, options.bin)),
+        );
+    }
+
+    let histogram = trains.isi_histogram(options.bin);
+    println!("\nISI histogram (bin {}):", histogram.bin_width);
+    let widest = histogram.counts.iter().copied().max().unwrap_or(0).max(1);
+    for (bin, count) in histogram.counts.iter().enumerate() {
+        let from = bin as u32 * histogram.bin_width;
+        println!("{:>6}..{:<6} {count:>6} {}", from, from.saturating_add(histogram.bin_width), "#".repeat((count * 40 / widest) as usize));
+    }
+
+    let ids: Vec<_> = trains.neurons().collect();
+    let pairs: Vec<_> = ids.iter().enumerate().flat_map(|(i, a)| ids[i + 1..].iter().map(move |b| (*a, *b))).collect();
+    if !pairs.is_empty() {
+        let correlations: Vec<f32> = pairs.iter().filter_map(|(a, b)| trains.correlation(*a, *b, options.bin)).collect();
+        let mean = |values: &[f32]| (!values.is_empty()).then(|| values.iter().sum::<f32>() / values.len() as f32);
+        let rossum: Vec<f32> = pairs.iter().map(|(a, b)| van_rossum_distance(trains.train(*a), trains.train(*b), options.tau)).collect();
+        let victor: Vec<f32> =
+            pairs.iter().map(|(a, b)| victor_purpura_distance(trains.train(*a), trains.train(*b), options.cost)).collect();
+        let mut correlogram = vec![0; 2 * options.lag as usize + 1];
+        for (a, b) in &pairs {
+            for (total, count) in correlogram.iter_mut().zip(trains.cross_correlogram(*a, *b, options.lag)) {
+                *total += count;
+            }
+        }
+
+        println!("\npairs: {}", pairs.len());
+        println!("mean correlation (bin {}): {}", options.bin, format_optional(mean(&correlations)));
+        println!("mean van Rossum distance (tau {}): {}", options.tau, format_optional(mean(&rossum)));
+        println!("mean Victor-Purpura distance (cost {}): {}", options.cost, format_optional(mean(&victor)));
+        println!("summed cross-correlogram (lag -{0}..={0}): {correlogram:?}", options.lag);
+    }
+    Ok(())
+}
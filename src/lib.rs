@@ -0,0 +1 @@
+pub mod neural_sim;
@@ -1,11 +1,9 @@
-use neural_sim::{ControllingUnit, Director, VecOrValueFloat, BatchLinkingRule};
-use neural_sim::neuron::lif_neuron::LifNeuron;
-use neural_sim::error::Error;
-use neural_sim::Simulation;
+use rust_nn_framewrk::neural_sim::{ControllingUnit, Director, VecOrValueFloat, BatchLinkingRule};
+use rust_nn_framewrk::neural_sim::neuron::lif_neuron::{LifNeuron, LifParams};
+use rust_nn_framewrk::neural_sim::error::Error;
+use rust_nn_framewrk::neural_sim::Simulation;
+use rust_nn_framewrk::neural_sim::population::Shape;
 
-use crate::neural_sim::neuron::{CommonlyCreateable, TimeDependent};
-
-mod neural_sim;
 
 fn main() -> Result<(), Error> {
     let sim_time: u32 = 15;
@@ -15,21 +13,17 @@ fn main() -> Result<(), Error> {
     let director: Director = Director::new(sim_time, 0).unwrap();
     let director: &mut Director = sim.register_director(director).unwrap();
 
-    let mut layer_1 = LifNeuron::batch_create_new(lin_layers_size[0].try_into().unwrap(), 0.6);
-    let layer_2 = LifNeuron::batch_create_new(lin_layers_size[1].try_into().unwrap(), 0.6);
-
-    for neuron in &mut layer_1 {
-        neuron.plan_init_impulses(vec![1, 3]);
-    }
-
-    let layer_1 = LifNeuron::register_batch(layer_1, director);
-    let layer_2 = LifNeuron::register_batch(layer_2, director);
+    let layer_1 = director.add_population::<LifNeuron>("input", Shape::D1(lin_layers_size[0].try_into()?), LifParams::new(0.6))?;
+    let layer_2 = director.add_population::<LifNeuron>("output", Shape::D1(lin_layers_size[1].try_into()?), LifParams::new(0.6))?;
+    director.schedule_spikes(&layer_1, &[1, 3])?;
+    director.record_spikes(&layer_1)?;
+    director.record_spikes(&layer_2)?;
 
     director.create_links_by_rule(
         &layer_1,
         &layer_2,
         VecOrValueFloat::Val(0.3),
-        BatchLinkingRule::UserDefined(|x, y| x == y),
+        BatchLinkingRule::OneToOne,
     )?;
     director.create_links_by_rule(
         &layer_1[0..1],
@@ -40,5 +34,10 @@ fn main() -> Result<(), Error> {
 
     sim.start()?;
 
+    let director = &sim.directors()[0];
+    for population in director.populations() {
+        println!("{population}: {} spikes", director.recorded_spikes_of(population).len());
+    }
+
     Ok(())
 }
@@ -0,0 +1,249 @@
+use std::collections::BTreeMap;
+use std::io::{BufRead, Write};
+use std::ops::Range;
+
+use super::NeuronUniqueId;
+use super::error::Error;
+
+/// Counts of values falling into consecutive bins of `bin_width`, the first one starting at 0.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Histogram {
+    pub bin_width: u32,
+    pub counts: Vec<u32>,
+}
+
+impl Histogram {
+    fn from_values(values: impl Iterator<Item = u32>, bin_width: u32) -> Self {
+        let bin_width = bin_width.max(1);
+        let mut counts = Vec::new();
+        for value in values {
+            let bin = (value / bin_width) as usize;
+            if bin >= counts.len() {
+                counts.resize(bin + 1, 0);
+            }
+            counts[bin] += 1;
+        }
+        Self { bin_width, counts }
+    }
+}
+
+/// Spike trains of a set of neurons over a window of time steps.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SpikeTrains {
+    trains: BTreeMap<NeuronUniqueId, Vec<u32>>,
+    window: Range<u32>,
+}
+
+impl SpikeTrains {
+    /// Groups `(neuron_id, time_step)` pairs as recorded by a director, keeping those inside `window`.
+    pub fn new(spikes: &[(NeuronUniqueId, u32)], window: Range<u32>) -> Self {
+        let mut trains: BTreeMap<NeuronUniqueId, Vec<u32>> = BTreeMap::new();
+        for (id, time_step) in spikes.iter().filter(|(_, time_step)| window.contains(time_step)) {
+            trains.entry(*id).or_default().push(*time_step);
+        }
+        for train in trains.values_mut() {
+            train.sort_unstable();
+        }
+        Self { trains, window }
+    }
+
+    /// Adds neurons that may have stayed silent, so they count towards population measures.
+    pub fn with_neurons(mut self, ids: &[NeuronUniqueId]) -> Self {
+        for id in ids {
+            self.trains.entry(*id).or_default();
+        }
+        self
+    }
+
+    pub fn neurons(&self) -> impl Iterator<Item = NeuronUniqueId> + '_ {
+        self.trains.keys().copied()
+    }
+
+    pub fn train(&self, id: NeuronUniqueId) -> &[u32] {
+        self.trains.get(&id).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn window(&self) -> Range<u32> {
+        self.window.clone()
+    }
+
+    pub fn duration(&self) -> u32 {
+        self.window.end.saturating_sub(self.window.start)
+    }
+
+    pub fn spike_count(&self) -> usize {
+        self.trains.values().map(Vec::len).sum()
+    }
+
+    /// Spikes per step of one neuron.
+    pub fn rate(&self, id: NeuronUniqueId) -> f32 {
+        if self.duration() == 0 {
+            return 0.;
+        }
+        self.train(id).len() as f32 / self.duration() as f32
+    }
+
+    /// Mean rate over all neurons, in spikes per step and neuron.
+    pub fn population_rate(&self) -> f32 {
+        if self.trains.is_empty() || self.duration() == 0 {
+            return 0.;
+        }
+        self.spike_count() as f32 / (self.trains.len() as f32 * self.duration() as f32)
+    }
+
+    /// Population rate in consecutive bins of `bin_width` steps.
+    pub fn population_rate_histogram(&self, bin_width: u32) -> Vec<f32> {
+        let bin_width = bin_width.max(1);
+        let bins = self.duration().div_ceil(bin_width) as usize;
+        let mut counts = vec![0.; bins];
+        for time_step in self.trains.values().flatten() {
+            counts[((time_step - self.window.start) / bin_width) as usize] += 1.;
+        }
+        let neurons = self.trains.len().max(1) as f32;
+        counts
+            .iter()
+            .enumerate()
+            .map(|(bin, count)| {
+                let width = (self.duration() - bin as u32 * bin_width).min(bin_width);
+                count / (neurons * width as f32)
+            })
+            .collect()
+    }
+
+    pub fn inter_spike_intervals(&self, id: NeuronUniqueId) -> Vec<u32> {
+        self.train(id).windows(2).map(|pair| pair[1] - pair[0]).collect()
+    }
+
+    /// Histogram of the inter-spike intervals of all neurons.
+    pub fn isi_histogram(&self, bin_width: u32) -> Histogram {
+        Histogram::from_values(self.neurons().flat_map(|id| self.inter_spike_intervals(id)), bin_width)
+    }
+
+    /// Coefficient of variation of the inter-spike intervals, `None` with fewer than two intervals.
+    pub fn cv(&self, id: NeuronUniqueId) -> Option<f32> {
+        let intervals: Vec<f32> = self.inter_spike_intervals(id).into_iter().map(|isi| isi as f32).collect();
+        let (mean, variance) = mean_and_variance(&intervals)?;
+        (intervals.len() >= 2 && mean > 0.).then(|| variance.sqrt() / mean)
+    }
+
+    /// Variance over mean of spike counts in consecutive windows of `window` steps.
+    pub fn fano_factor(&self, id: NeuronUniqueId, window: u32) -> Option<f32> {
+        let counts = self.binned_counts(id, window);
+        let (mean, variance) = mean_and_variance(&counts)?;
+        (counts.len() >= 2 && mean > 0.).then_some(variance / mean)
+    }
+
+    /// Spike counts of one neuron in bins of `bin_width` steps; an incomplete last bin is dropped.
+    pub fn binned_counts(&self, id: NeuronUniqueId, bin_width: u32) -> Vec<f32> {
+        let bin_width = bin_width.max(1);
+        let mut counts = vec![0.; (self.duration() / bin_width) as usize];
+        for time_step in self.train(id) {
+            if let Some(count) = counts.get_mut(((time_step - self.window.start) / bin_width) as usize) {
+                *count += 1.;
+            }
+        }
+        counts
+    }
+
+    /// Coincidences of spikes of `b` at lag `t_b - t_a`, for lags `-max_lag..=max_lag`.
+    pub fn cross_correlogram(&self, a: NeuronUniqueId, b: NeuronUniqueId, max_lag: u32) -> Vec<u32> {
+        let mut counts = vec![0; 2 * max_lag as usize + 1];
+        let train_b = self.train(b);
+        for time_a in self.train(a) {
+            let first = train_b.partition_point(|time_b| time_b.saturating_add(max_lag) < *time_a);
+            for time_b in train_b[first..].iter().take_while(|time_b| **time_b <= time_a.saturating_add(max_lag)) {
+                counts[(*time_b as i64 - *time_a as i64 + max_lag as i64) as usize] += 1;
+            }
+        }
+        counts
+    }
+
+    /// Pearson correlation of spike counts of two neurons in bins of `bin_width` steps.
+    pub fn correlation(&self, a: NeuronUniqueId, b: NeuronUniqueId, bin_width: u32) -> Option<f32> {
+        let (counts_a, counts_b) = (self.binned_counts(a, bin_width), self.binned_counts(b, bin_width));
+        let (mean_a, variance_a) = mean_and_variance(&counts_a)?;
+        let (mean_b, variance_b) = mean_and_variance(&counts_b)?;
+        if variance_a == 0. || variance_b == 0. {
+            return None;
+        }
+        let covariance = counts_a.iter().zip(&counts_b).map(|(x, y)| (x - mean_a) * (y - mean_b)).sum::<f32>() / counts_a.len() as f32;
+        Some(covariance / (variance_a * variance_b).sqrt())
+    }
+
+    /// Golomb synchrony measure: variance of the population-averaged binned activity over the
+    /// mean variance of single neurons, square rooted. 1 is full synchrony, near 0 asynchrony.
+    pub fn synchrony(&self, bin_width: u32) -> Option<f32> {
+        let counts: Vec<Vec<f32>> = self.neurons().map(|id| self.binned_counts(id, bin_width)).collect();
+        let bins = counts.first()?.len();
+        let average: Vec<f32> = (0..bins).map(|bin| counts.iter().map(|c| c[bin]).sum::<f32>() / counts.len() as f32).collect();
+        let (_, population_variance) = mean_and_variance(&average)?;
+        let single_variance =
+            counts.iter().filter_map(|c| mean_and_variance(c).map(|(_, variance)| variance)).sum::<f32>() / counts.len() as f32;
+        (single_variance > 0.).then(|| (population_variance / single_variance).sqrt())
+    }
+}
+
+fn mean_and_variance(values: &[f32]) -> Option<(f32, f32)> {
+    if values.is_empty() {
+        return None;
+    }
+    let mean = values.iter().sum::<f32>() / values.len() as f32;
+    let variance = values.iter().map(|value| (value - mean).powi(2)).sum::<f32>() / values.len() as f32;
+    Some((mean, variance))
+}
+
+/// van Rossum distance between two spike trains filtered with an exponential kernel of time
+/// constant `tau` steps.
+pub fn van_rossum_distance(a: &[u32], b: &[u32], tau: f32) -> f32 {
+    let kernel_sum = |x: &[u32], y: &[u32]| -> f32 {
+        x.iter()
+            .flat_map(|s| y.iter().map(move |t| (-(s.abs_diff(*t) as f32) / tau).exp()))
+            .sum()
+    };
+    ((kernel_sum(a, a) + kernel_sum(b, b) - 2. * kernel_sum(a, b)) / 2.).max(0.).sqrt()
+}
+
+/// Victor-Purpura distance: cheapest way to turn `a` into `b`, where adding or removing a
+/// spike costs 1 and moving one by `dt` steps costs `cost * dt`.
+pub fn victor_purpura_distance(a: &[u32], b: &[u32], cost: f32) -> f32 {
+    let mut previous: Vec<f32> = (0..=b.len()).map(|j| j as f32).collect();
+    for (i, spike_a) in a.iter().enumerate() {
+        let mut current = vec![i as f32 + 1.; b.len() + 1];
+        for (j, spike_b) in b.iter().enumerate() {
+            let shift = previous[j] + cost * spike_a.abs_diff(*spike_b) as f32;
+            current[j + 1] = (previous[j + 1] + 1.).min(current[j] + 1.).min(shift);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Writes spikes as `neuron_id,time_step` lines after a header line.
+pub fn write_spikes_csv(mut writer: impl Write, spikes: &[(NeuronUniqueId, u32)]) -> Result<(), Error> {
+    writeln!(writer, "neuron_id,time_step")?;
+    for (id, time_step) in spikes {
+        writeln!(writer, "{id},{time_step}")?;
+    }
+    Ok(())
+}
+
+/// Reads `neuron_id,time_step` lines; a header line, blank lines and `#` comments are skipped.
+pub fn read_spikes_csv(reader: impl BufRead) -> Result<Vec<(NeuronUniqueId, u32)>, Error> {
+    let mut spikes = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let parsed = line
+            .split_once(',')
+            .and_then(|(id, time_step)| Some((id.trim().parse().ok()?, time_step.trim().parse().ok()?)));
+        match parsed {
+            Some(spike) => spikes.push(spike),
+            None if index == 0 => continue,
+            None => return Err(Error::InvalidCsv { line: index + 1 }),
+        }
+    }
+    Ok(spikes)
+}
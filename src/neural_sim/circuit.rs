@@ -0,0 +1,97 @@
+use super::error::Error;
+use super::neuron::lif_neuron::{LifNeuron, LifParams};
+use super::population::{Population, Shape};
+use super::{BatchLinkingRule, ControllingUnit, Director, VecOrValueFloat};
+
+/// How the inhibitory side of a winner-take-all circuit is laid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WtaMode {
+    /// k-winners-take-all: every excitatory neuron has an inhibitory partner that counts the
+    /// spikes of all other excitatory neurons and inhibits its partner only, once `k` of them
+    /// fired. The `k` most active neurons see at most `k - 1` others firing, so they keep
+    /// their undisturbed rhythm while the rest is held down. With `k` 1 this is the lateral
+    /// inhibition of Diehl & Cook.
+    Lateral { k: usize },
+    /// One inhibitory neuron that fires once `k` excitatory neurons fired and then inhibits the
+    /// whole excitatory population, winners included. Cheaper than `Lateral`, but it only
+    /// limits how many neurons fire together and does not pick winners.
+    Pooled { k: usize },
+}
+
+/// Builds the inhibitory layer of a winner-take-all circuit around an excitatory population.
+///
+/// Inhibitory neurons count excitatory spikes: their threshold is `k - 0.5` times the
+/// excitation weight. They do not leak over steps (`beta` 0 by default), so they only count
+/// the spikes of the current step; a `beta` above 0 lets them count spikes of the last few
+/// steps, which neurons firing at different steps need to be compared.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WinnerTakeAll {
+    excitatory: Population,
+    mode: WtaMode,
+    excitation: f32,
+    inhibition: f32,
+    beta: f32,
+    vectorized: bool,
+}
+
+impl WinnerTakeAll {
+    pub fn new(excitatory: &Population, mode: WtaMode) -> Self {
+        Self { excitatory: excitatory.clone(), mode, excitation: 1., inhibition: -1., beta: 0., vectorized: false }
+    }
+
+    /// Weight of the excitatory to inhibitory links.
+    pub fn with_excitation(mut self, weight: f32) -> Self {
+        self.excitation = weight;
+        self
+    }
+
+    /// Weight of the inhibitory to excitatory links, must be negative.
+    pub fn with_inhibition(mut self, weight: f32) -> Self {
+        self.inhibition = weight;
+        self
+    }
+
+    /// Leak of the inhibitory neurons.
+    pub fn with_beta(mut self, beta: f32) -> Self {
+        self.beta = beta;
+        self
+    }
+
+    /// Creates the inhibitory layer as a vectorized population instead of `LifNeuron`s.
+    pub fn vectorized(mut self) -> Self {
+        self.vectorized = true;
+        self
+    }
+
+    /// Adds the inhibitory population named `name` to `director` and wires it up.
+    pub fn build(&self, director: &mut Director, name: &str) -> Result<Population, Error> {
+        if self.excitation <= 0. {
+            return Err(Error::InvalidCircuit("excitation weight must be positive"));
+        }
+        if self.inhibition >= 0. {
+            return Err(Error::InvalidCircuit("inhibition weight must be negative"));
+        }
+        let (shape, k) = match self.mode {
+            WtaMode::Lateral { k } => (self.excitatory.shape(), k),
+            WtaMode::Pooled { k } => (Shape::D1(1), k),
+        };
+        if k == 0 || k > self.excitatory.len() {
+            return Err(Error::InvalidCircuit("k must be between 1 and the excitatory population size"));
+        }
+        let threshold = self.excitation * (k as f32 - 0.5);
+        let params = LifParams::new(self.beta).with_threshold(threshold);
+        let inhibitory = if self.vectorized {
+            director.add_vectorized_population(name, shape, params)?
+        } else {
+            director.add_population::<LifNeuron>(name, shape, params)?
+        };
+
+        let (forward, backward) = match self.mode {
+            WtaMode::Lateral { .. } => (BatchLinkingRule::UserDefined(|i, j| i != j), BatchLinkingRule::OneToOne),
+            WtaMode::Pooled { .. } => (BatchLinkingRule::FullyConnected, BatchLinkingRule::FullyConnected),
+        };
+        director.create_links_by_rule(&self.excitatory, &inhibitory, VecOrValueFloat::Val(self.excitation), forward)?;
+        director.create_links_by_rule(&inhibitory, &self.excitatory, VecOrValueFloat::Val(self.inhibition), backward)?;
+        Ok(inhibitory)
+    }
+}
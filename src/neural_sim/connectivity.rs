@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+
+use super::{ForwardOneToManyConnection, NeuronUniqueId};
+
+/// Connectivity frozen into compressed sparse rows. Neuron ids are booked sequentially
+/// from `first_id`, so a source id offset by it indexes `row_offsets` directly.
+pub(super) struct CsrConnectivity {
+    first_id: NeuronUniqueId,
+    row_offsets: Vec<usize>,
+    targets: Vec<NeuronUniqueId>,
+    weights: Vec<f32>,
+}
+
+impl CsrConnectivity {
+    pub(super) fn from_map(
+        map: &HashMap<NeuronUniqueId, ForwardOneToManyConnection>,
+        first_id: NeuronUniqueId,
+        neuron_count: usize,
+    ) -> Self {
+        let mut row_offsets = Vec::with_capacity(neuron_count + 1);
+        let mut targets = Vec::new();
+        let mut weights = Vec::new();
+        row_offsets.push(0);
+        for source in first_id..first_id + neuron_count as NeuronUniqueId {
+            if let Some(pairs) = map.get(&source) {
+                for pair in pairs {
+                    targets.push(pair.id);
+                    weights.push(pair.weight);
+                }
+            }
+            row_offsets.push(targets.len());
+        }
+        Self {
+            first_id,
+            row_offsets,
+            targets,
+            weights,
+        }
+    }
+
+    fn row_range(&self, source: NeuronUniqueId) -> std::ops::Range<usize> {
+        let Some(source) = source.checked_sub(self.first_id).map(|source| source as usize) else {
+            return 0..0;
+        };
+        match (self.row_offsets.get(source), self.row_offsets.get(source + 1)) {
+            (Some(start), Some(end)) => *start..*end,
+            _ => 0..0,
+        }
+    }
+
+    pub(super) fn row(&self, source: NeuronUniqueId) -> (&[NeuronUniqueId], &[f32]) {
+        let range = self.row_range(source);
+        (&self.targets[range.clone()], &self.weights[range])
+    }
+
+    /// `position` counts the links of `source` in the order they were created, as rows keep it.
+    pub(super) fn set_weight_at(&mut self, source: NeuronUniqueId, position: usize, weight: f32) {
+        let index = self.row_range(source).start + position;
+        self.weights[index] = weight;
+    }
+}
+
+/// Per-target accumulator of the input delivered during one delta cycle, so every target
+/// is touched once no matter how many synapses delivered to it.
+///
+/// Synaptic input of all spikes fired in the same delta cycle, plus the stimuli and injected
+/// currents of the step on its first cycle, is summed before the target sees it. The threshold
+/// is then checked once against the sum: two inputs of 0.6 fire a neuron with threshold 1, and
+/// an inhibitory input cancels an excitatory one of the same cycle whatever order they came in.
+pub(super) struct InputBuffer {
+    first_id: NeuronUniqueId,
+    values: Vec<f32>,
+    pending: Vec<bool>,
+    touched: Vec<NeuronUniqueId>,
+}
+
+impl InputBuffer {
+    /// Buffer for the neurons of a director whose ids start at `first_id`.
+    pub(super) fn new(first_id: NeuronUniqueId) -> Self {
+        Self {
+            first_id,
+            values: Vec::new(),
+            pending: Vec::new(),
+            touched: Vec::new(),
+        }
+    }
+
+    pub(super) fn add(&mut self, target: NeuronUniqueId, value: f32) {
+        let index = (target - self.first_id) as usize;
+        if index >= self.values.len() {
+            self.values.resize(index + 1, 0.);
+            self.pending.resize(index + 1, false);
+        }
+        if !self.pending[index] {
+            self.pending[index] = true;
+            self.touched.push(target);
+        }
+        self.values[index] += value;
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.touched.is_empty()
+    }
+
+    /// Takes accumulated `(target, input)` pairs in the order targets were first touched.
+    pub(super) fn drain(&mut self) -> Vec<(NeuronUniqueId, f32)> {
+        let mut drained = Vec::with_capacity(self.touched.len());
+        for target in self.touched.drain(..) {
+            let index = (target - self.first_id) as usize;
+            drained.push((target, self.values[index]));
+            self.values[index] = 0.;
+            self.pending[index] = false;
+        }
+        drained
+    }
+}
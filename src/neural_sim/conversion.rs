@@ -0,0 +1,353 @@
+use std::path::Path;
+
+use super::current::CurrentSource;
+use super::error::Error;
+use super::neuron::lif_neuron::LifParams;
+use super::population::{Population, Shape};
+use super::{BatchLinkingRule, ControllingUnit, Director, VecOrValueFloat};
+
+const NPY_MAGIC: &[u8] = b"\x93NUMPY";
+
+/// Array read from a NumPy `.npy` file, converted to `f32`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NpyArray {
+    shape: Vec<usize>,
+    data: Vec<f32>,
+}
+
+impl NpyArray {
+    pub fn new(shape: Vec<usize>, data: Vec<f32>) -> Result<Self, Error> {
+        if element_count(&shape)? != data.len() {
+            return Err(Error::Npy("data length does not match shape"));
+        }
+        Ok(Self { shape, data })
+    }
+
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::parse(&std::fs::read(path)?)
+    }
+
+    /// Parses little-endian `f4` or `f8` arrays stored in C order.
+    pub fn parse(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() < 10 || &bytes[..6] != NPY_MAGIC {
+            return Err(Error::Npy("missing magic string"));
+        }
+        let (header_len, header_start) = match bytes[6] {
+            1 => (u16::from_le_bytes([bytes[8], bytes[9]]) as usize, 10usize),
+            2 | 3 if bytes.len() >= 12 => (u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]) as usize, 12),
+            _ => return Err(Error::Npy("unsupported format version")),
+        };
+        let header_end = header_start.checked_add(header_len).ok_or(Error::Npy("truncated header"))?;
+        let header = bytes
+            .get(header_start..header_end)
+            .and_then(|header| std::str::from_utf8(header).ok())
+            .ok_or(Error::Npy("truncated header"))?;
+
+        let descr = header_value(header, "descr")
+            .and_then(|value| value.strip_prefix('\''))
+            .and_then(|value| value.split('\'').next())
+            .ok_or(Error::Npy("header has no descr"))?;
+        let item_size = match descr {
+            "<f4" => 4,
+            "<f8" => 8,
+            _ => return Err(Error::Npy("only little-endian f4 and f8 arrays are supported")),
+        };
+        if header_value(header, "fortran_order").is_some_and(|value| value.starts_with("True")) {
+            return Err(Error::Npy("fortran ordered arrays are not supported"));
+        }
+        let shape = header_value(header, "shape")
+            .and_then(|value| value.strip_prefix('('))
+            .and_then(|value| value.split(')').next())
+            .ok_or(Error::Npy("header has no shape"))?
+            .split(',')
+            .map(str::trim)
+            .filter(|dimension| !dimension.is_empty())
+            .map(|dimension| dimension.parse::<usize>().map_err(|_| Error::Npy("invalid shape")))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let body = &bytes[header_end..];
+        let size = element_count(&shape)?
+            .checked_mul(item_size)
+            .ok_or(Error::Npy("shape is too large"))?;
+        if body.len() < size {
+            return Err(Error::Npy("truncated data"));
+        }
+        let data = body[..size]
+            .chunks_exact(item_size)
+            .map(|chunk| match item_size {
+                4 => f32::from_le_bytes(chunk.try_into().unwrap()),
+                _ => f64::from_le_bytes(chunk.try_into().unwrap()) as f32,
+            })
+            .collect();
+        Ok(Self { shape, data })
+    }
+
+    pub fn shape(&self) -> &[usize] {
+        &self.shape
+    }
+
+    pub fn data(&self) -> &[f32] {
+        &self.data
+    }
+}
+
+/// Number of elements of an array of `shape`, an error if it does not fit in memory.
+fn element_count(shape: &[usize]) -> Result<usize, Error> {
+    shape
+        .iter()
+        .try_fold(1usize, |count, dimension| count.checked_mul(*dimension))
+        .ok_or(Error::Npy("shape is too large"))
+}
+
+/// Text right after `'key':` in a header dictionary.
+fn header_value<'a>(header: &'a str, key: &str) -> Option<&'a str> {
+    let start = header.find(&format!("'{key}'"))? + key.len() + 2;
+    Some(header[start..].trim_start().strip_prefix(':')?.trim_start())
+}
+
+/// Layer of a trained ReLU network, weights laid out like PyTorch stores them.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnnLayer {
+    /// `weights` of shape `(outputs, inputs)`.
+    Dense { weights: NpyArray, bias: Option<NpyArray> },
+    /// `weights` of shape `(out_channels, in_channels, kernel_height, kernel_width)`.
+    Conv2d { weights: NpyArray, bias: Option<NpyArray>, stride: usize, padding: usize },
+}
+
+/// Converts a feed-forward ReLU network into populations of integrate-and-fire neurons driven
+/// by rate-coded input.
+///
+/// Inputs are expected in `[0, 1]` and encoded with `Sample::poisson`, so a classifier built on
+/// the first and last returned population reads the converted network out by spike count.
+/// Data-based threshold balancing rescales every layer by the maximum (or a percentile) of its
+/// ANN activations on calibration data, so firing rates stay below one spike per step.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnnToSnn {
+    input_shape: Shape,
+    layers: Vec<AnnLayer>,
+    shapes: Vec<Shape>,
+    scales: Vec<f32>,
+    percentile: f32,
+    beta: f32,
+    max_rate: f32,
+}
+
+impl AnnToSnn {
+    /// `input_shape` is `D3(channels, height, width)` or `D2(height, width)` in front of
+    /// convolutions.
+    pub fn new(input_shape: Shape) -> Self {
+        Self {
+            shapes: vec![input_shape],
+            input_shape,
+            layers: Vec::new(),
+            scales: Vec::new(),
+            percentile: 100.,
+            beta: 1.,
+            max_rate: 1.,
+        }
+    }
+
+    /// Percentile of positive activations used as the layer scale, 100 takes the maximum.
+    pub fn with_percentile(mut self, percentile: f32) -> Self {
+        self.percentile = percentile.clamp(0., 100.);
+        self
+    }
+
+    /// Leak of the built neurons, 1 makes them non-leaky integrate-and-fire neurons.
+    pub fn with_beta(mut self, beta: f32) -> Self {
+        self.beta = beta;
+        self
+    }
+
+    /// Spike probability per step of an input of 1, needed to turn biases into currents.
+    pub fn with_max_rate(mut self, max_rate: f32) -> Self {
+        self.max_rate = max_rate;
+        self
+    }
+
+    pub fn dense(mut self, weights: NpyArray, bias: Option<NpyArray>) -> Result<Self, Error> {
+        let inputs = self.shapes.last().unwrap().len();
+        let &[outputs, found] = weights.shape() else {
+            return Err(Error::Conversion("dense weights must have two dimensions"));
+        };
+        if found != inputs {
+            return Err(Error::Conversion("dense weights do not fit the previous layer"));
+        }
+        check_bias(&bias, outputs)?;
+        self.shapes.push(Shape::D1(outputs));
+        self.layers.push(AnnLayer::Dense { weights, bias });
+        Ok(self)
+    }
+
+    pub fn conv2d(mut self, weights: NpyArray, bias: Option<NpyArray>, stride: usize, padding: usize) -> Result<Self, Error> {
+        let (channels, height, width) = match *self.shapes.last().unwrap() {
+            Shape::D3(channels, height, width) => (channels, height, width),
+            Shape::D2(height, width) => (1, height, width),
+            Shape::D1(_) => return Err(Error::Conversion("convolution needs a two or three dimensional input")),
+        };
+        let &[out_channels, in_channels, kernel_height, kernel_width] = weights.shape() else {
+            return Err(Error::Conversion("convolution weights must have four dimensions"));
+        };
+        if in_channels != channels {
+            return Err(Error::Conversion("convolution weights do not fit the previous layer"));
+        }
+        if stride == 0 {
+            return Err(Error::Conversion("convolution stride must be positive"));
+        }
+        if kernel_height > height + 2 * padding || kernel_width > width + 2 * padding {
+            return Err(Error::Conversion("convolution kernel is larger than its input"));
+        }
+        check_bias(&bias, out_channels)?;
+        let out_height = (height + 2 * padding - kernel_height) / stride + 1;
+        let out_width = (width + 2 * padding - kernel_width) / stride + 1;
+        self.shapes.push(Shape::D3(out_channels, out_height, out_width));
+        self.layers.push(AnnLayer::Conv2d { weights, bias, stride, padding });
+        Ok(self)
+    }
+
+    /// ReLU activations of every layer for `input`, the input itself first.
+    pub fn activations(&self, input: &[f32]) -> Result<Vec<Vec<f32>>, Error> {
+        if input.len() != self.input_shape.len() {
+            return Err(Error::SampleShape { expected: self.input_shape.len(), found: input.len() });
+        }
+        let mut activations = vec![input.to_vec()];
+        for (index, layer) in self.layers.iter().enumerate() {
+            let mut output = vec![0.; self.shapes[index + 1].len()];
+            if let Some(bias) = layer_bias(layer, output.len())? {
+                output = bias;
+            }
+            self.for_each_synapse(index, |source, destination, weight| {
+                output[destination] += weight * activations[index][source];
+            });
+            output.iter_mut().for_each(|value| *value = value.max(0.));
+            activations.push(output);
+        }
+        Ok(activations)
+    }
+
+    /// Sets the scale of every layer from `calibration` inputs and returns the scales.
+    pub fn balance(&mut self, calibration: &[Vec<f32>]) -> Result<&[f32], Error> {
+        let mut per_layer: Vec<Vec<f32>> = vec![Vec::new(); self.layers.len()];
+        for input in calibration {
+            for (values, activations) in per_layer.iter_mut().zip(self.activations(input)?.into_iter().skip(1)) {
+                values.extend(activations.into_iter().filter(|value| *value > 0.));
+            }
+        }
+        self.scales = per_layer
+            .into_iter()
+            .map(|mut values| {
+                if values.is_empty() {
+                    return 1.;
+                }
+                values.sort_by(f32::total_cmp);
+                let rank = (self.percentile / 100. * values.len() as f32).ceil() as usize;
+                values[rank.clamp(1, values.len()) - 1]
+            })
+            .collect();
+        Ok(&self.scales)
+    }
+
+    /// Builds the input population and one population per layer, named `{name}_input` and
+    /// `{name}_{layer}`. Without a previous `balance` weights are used as they are.
+    pub fn build(&self, director: &mut Director, name: &str) -> Result<Vec<Population>, Error> {
+        let params = LifParams::new(self.beta);
+        let mut populations = vec![director.add_vectorized_population(&format!("{name}_input"), self.input_shape, params)?];
+        for (index, layer) in self.layers.iter().enumerate() {
+            let scale = self.scales.get(index).copied().unwrap_or(1.);
+            let previous_scale = index.checked_sub(1).and_then(|previous| self.scales.get(previous)).copied().unwrap_or(1.);
+            let population = director.add_vectorized_population(&format!("{name}_{}", index + 1), self.shapes[index + 1], params)?;
+
+            let mut triplets = Vec::new();
+            self.for_each_synapse(index, |source, destination, weight| {
+                if weight != 0. {
+                    triplets.push((source, destination, weight * previous_scale / scale));
+                }
+            });
+            director.create_links_by_rule(
+                &populations[index],
+                &population,
+                VecOrValueFloat::Sparse(triplets),
+                BatchLinkingRule::FullyConnected,
+            )?;
+
+            if let Some(bias) = layer_bias(layer, population.len())? {
+                for (id, value) in population.iter().zip(bias) {
+                    if value != 0. {
+                        director.inject_current(&[*id], CurrentSource::Bias(value / scale * self.max_rate))?;
+                    }
+                }
+            }
+            populations.push(population);
+        }
+        Ok(populations)
+    }
+
+    /// Calls `synapse(source, destination, weight)` with flat indices for every weight of `layer`.
+    fn for_each_synapse(&self, layer: usize, mut synapse: impl FnMut(usize, usize, f32)) {
+        match &self.layers[layer] {
+            AnnLayer::Dense { weights, .. } => {
+                let inputs = weights.shape()[1];
+                for (index, weight) in weights.data().iter().enumerate() {
+                    synapse(index % inputs, index / inputs, *weight);
+                }
+            }
+            AnnLayer::Conv2d { weights, stride, padding, .. } => {
+                let (height, width) = match self.shapes[layer] {
+                    Shape::D3(_, height, width) | Shape::D2(height, width) => (height, width),
+                    Shape::D1(_) => unreachable!("convolutions are checked to have spatial input"),
+                };
+                let Shape::D3(out_channels, out_height, out_width) = self.shapes[layer + 1] else {
+                    unreachable!("convolutions produce three dimensional output")
+                };
+                let &[_, in_channels, kernel_height, kernel_width] = weights.shape() else {
+                    unreachable!("convolution weights are checked to have four dimensions")
+                };
+                for out_channel in 0..out_channels {
+                    for out_y in 0..out_height {
+                        for out_x in 0..out_width {
+                            let destination = (out_channel * out_height + out_y) * out_width + out_x;
+                            for in_channel in 0..in_channels {
+                                for kernel_y in 0..kernel_height {
+                                    let Some(y) = (out_y * stride + kernel_y).checked_sub(*padding).filter(|y| *y < height) else {
+                                        continue;
+                                    };
+                                    for kernel_x in 0..kernel_width {
+                                        let Some(x) = (out_x * stride + kernel_x).checked_sub(*padding).filter(|x| *x < width) else {
+                                            continue;
+                                        };
+                                        let source = (in_channel * height + y) * width + x;
+                                        let weight = weights.data()
+                                            [((out_channel * in_channels + in_channel) * kernel_height + kernel_y) * kernel_width + kernel_x];
+                                        synapse(source, destination, weight);
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn check_bias(bias: &Option<NpyArray>, expected: usize) -> Result<(), Error> {
+    match bias {
+        Some(bias) if bias.data().is_empty() || bias.data().len() != expected => Err(Error::Conversion("bias does not fit the layer")),
+        _ => Ok(()),
+    }
+}
+
+/// Bias of every one of the `outputs` neurons of `layer`; a bias value covers the
+/// `outputs / bias.len()` consecutive neurons of one channel.
+fn layer_bias(layer: &AnnLayer, outputs: usize) -> Result<Option<Vec<f32>>, Error> {
+    let (AnnLayer::Dense { bias, .. } | AnnLayer::Conv2d { bias, .. }) = layer;
+    let Some(bias) = bias else {
+        return Ok(None);
+    };
+    let values = bias.data();
+    if values.is_empty() || !outputs.is_multiple_of(values.len()) {
+        return Err(Error::Conversion("bias does not fit the layer"));
+    }
+    let per_channel = outputs / values.len();
+    Ok(Some((0..outputs).map(|position| values[position / per_channel]).collect()))
+}
@@ -0,0 +1,119 @@
+use super::error::Error;
+use super::random::{Rng, Stream, derive_seed};
+use super::{Director, NeuronUniqueId};
+
+/// Current injected straight into the membrane of a neuron once per time step, after leak,
+/// the same way synaptic input is.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CurrentSource {
+    Bias(f32),
+    /// `(from_step, amplitude)` pairs; an amplitude holds until the next pair starts.
+    Steps(Vec<(u32, f32)>),
+    /// Linear change from `from` at `start` to `to` at `end`, zero outside of it.
+    Ramp { start: u32, end: u32, from: f32, to: f32 },
+    /// Independent gaussian sample every step.
+    WhiteNoise { mean: f32, std_dev: f32 },
+    /// Ornstein-Uhlenbeck process with stationary `mean` and `std_dev` and time constant `tau` in steps.
+    OrnsteinUhlenbeck { mean: f32, std_dev: f32, tau: f32 },
+}
+
+impl CurrentSource {
+    fn validate(&self) -> Result<(), Error> {
+        match self {
+            Self::Ramp { start, end, .. } if start > end => Err(Error::InvalidCurrent("ramp ends before it starts")),
+            Self::WhiteNoise { std_dev, .. } | Self::OrnsteinUhlenbeck { std_dev, .. } if *std_dev < 0. => {
+                Err(Error::InvalidCurrent("noise standard deviation is negative"))
+            }
+            Self::OrnsteinUhlenbeck { tau, .. } if *tau <= 0. => {
+                Err(Error::InvalidCurrent("Ornstein-Uhlenbeck time constant must be positive"))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+pub(super) struct Injection {
+    target: NeuronUniqueId,
+    source: CurrentSource,
+    rng: Rng,
+    /// Current value of the Ornstein-Uhlenbeck process.
+    state: f32,
+}
+
+impl Injection {
+    pub(super) fn target(&self) -> NeuronUniqueId {
+        self.target
+    }
+
+    /// Puts a noise process back to its mean; the random stream itself keeps going.
+    pub(super) fn reset(&mut self) {
+        if let CurrentSource::OrnsteinUhlenbeck { mean, .. } = self.source {
+            self.state = mean;
+        }
+    }
+
+    pub(super) fn sample(&mut self, time_step: u32) -> f32 {
+        match &self.source {
+            CurrentSource::Bias(value) => *value,
+            CurrentSource::Steps(steps) => steps
+                .iter()
+                .rev()
+                .find(|(from, _)| *from <= time_step)
+                .map_or(0., |(_, amplitude)| *amplitude),
+            CurrentSource::Ramp { start, end, from, to } => {
+                if time_step < *start || time_step > *end {
+                    0.
+                } else if start == end {
+                    *from
+                } else {
+                    let progress = (time_step - start) as f32 / (end - start) as f32;
+                    from + (to - from) * progress
+                }
+            }
+            CurrentSource::WhiteNoise { mean, std_dev } => self.rng.normal(*mean, *std_dev),
+            CurrentSource::OrnsteinUhlenbeck { mean, std_dev, tau } => {
+                /* exact discretization over one step, keeps the stationary std_dev for any tau */
+                let decay = (-1. / tau).exp();
+                let diffusion = std_dev * (1. - decay * decay).sqrt();
+                self.state = mean + (self.state - mean) * decay + self.rng.normal(0., diffusion);
+                self.state
+            }
+        }
+    }
+}
+
+impl Director {
+    /// Injects `source` into every neuron of `ids` (a population or a slice of one). Every neuron
+    /// gets its own noise stream, derived from its neuron stream and the number of injections
+    /// it already has, so injections into other neurons do not change it.
+    pub fn inject_current(&mut self, ids: &[NeuronUniqueId], source: CurrentSource) -> Result<(), Error> {
+        source.validate()?;
+        if let Some(id) = ids.iter().find(|id| !self.planner.is_booked(**id)) {
+            return Err(Error::UnknownNeuron(*id));
+        }
+        let mut source = source;
+        if let CurrentSource::Steps(steps) = &mut source {
+            steps.sort_by_key(|(from, _)| *from);
+        }
+        let initial_state = match source {
+            CurrentSource::OrnsteinUhlenbeck { mean, .. } => mean,
+            _ => 0.,
+        };
+        for id in ids {
+            let index = self.injections.iter().filter(|injection| injection.target == *id).count();
+            let stream = derive_seed(self.seed, Stream::Neuron(*id));
+            let seed = derive_seed(stream, Stream::Sub(index as u64));
+            self.injections.push(Injection {
+                target: *id,
+                source: source.clone(),
+                rng: Rng::seed_from_u64(seed),
+                state: initial_state,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn clear_currents(&mut self) {
+        self.injections.clear();
+    }
+}
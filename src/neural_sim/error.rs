@@ -1,10 +1,92 @@
+#[derive(Debug, PartialEq)]
+pub enum LinkCreateError {
+  UnknownSource(u32),
+  UnknownDestination(u32),
+  SelfConnection(u32),
+  Multapse { source: u32, destination: u32 },
+  LengthMismatch { sources: usize, destinations: usize },
+  RuleIndex { row: usize, column: usize },
+  Probability(f32),
+}
+
+impl std::fmt::Display for LinkCreateError {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    match self {
+      Self::UnknownSource(id) => write!(f, "source neuron {id} is not registered in this director"),
+      Self::UnknownDestination(id) => write!(f, "destination neuron {id} is not registered in this director"),
+      Self::SelfConnection(id) => write!(f, "self connection of neuron {id} is rejected by link policy"),
+      Self::Multapse { source, destination } => write!(f, "link from {source} to {destination} already exists"),
+      Self::LengthMismatch { sources, destinations } => write!(f, "one to one rule got {sources} sources and {destinations} destinations"),
+      Self::RuleIndex { row, column } => write!(f, "sparse rule pair ({row}, {column}) is out of range"),
+      Self::Probability(p) => write!(f, "link probability {p} is not in [0, 1]"),
+    }
+  }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum WeightError {
+  RowCount { expected: usize, found: usize },
+  ColumnCount { row: usize, expected: usize, found: usize },
+  SparseIndex { row: usize, column: usize, rows: usize, columns: usize },
+  InvalidDistribution(&'static str),
+}
+
+impl std::fmt::Display for WeightError {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    match self {
+      Self::RowCount { expected, found } => write!(f, "weight matrix has {found} rows, expected one per source ({expected})"),
+      Self::ColumnCount { row, expected, found } => write!(f, "weight matrix row {row} has {found} columns, expected one per destination ({expected})"),
+      Self::SparseIndex { row, column, rows, columns } => write!(f, "sparse weight ({row}, {column}) is outside of {rows}x{columns} sources/destinations"),
+      Self::InvalidDistribution(err) => write!(f, "invalid weight distribution: {err}"),
+    }
+  }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PopulationError {
+  ShapeMismatch { expected: usize, found: usize },
+  DuplicateName(String),
+  Dimensions { expected: usize, found: usize },
+  OutOfRange { dimension: usize, end: usize, size: usize },
+}
+
+impl std::fmt::Display for PopulationError {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    match self {
+      Self::ShapeMismatch { expected, found } => write!(f, "shape holds {expected} neurons, got {found}"),
+      Self::DuplicateName(name) => write!(f, "population {name} is already registered"),
+      Self::Dimensions { expected, found } => write!(f, "population has {expected} dimensions, got {found} coordinate ranges"),
+      Self::OutOfRange { dimension, end, size } => write!(f, "range end {end} is out of dimension {dimension} of size {size}"),
+    }
+  }
+}
+
 #[derive(Debug)]
 pub enum Error {
   Io(std::io::Error),
   Poison,
-  LinkCreate(&'static str),
+  LinkCreate(LinkCreateError),
   JoinHandle,
   FromInt,
+  AlreadyRunning,
+  UnknownNeuron(u32),
+  TimeStepInPast { requested: u32, current: u32 },
+  UnknownLink { source: u32, destination: u32 },
+  Weights(WeightError),
+  Population(PopulationError),
+  InvalidCurrent(&'static str),
+  SampleShape { expected: usize, found: usize },
+  UnknownDirector(usize),
+  DuplicateDirector(u32),
+  Training(&'static str),
+  Npy(&'static str),
+  Conversion(&'static str),
+  InvalidPlasticity(&'static str),
+  InvalidCircuit(&'static str),
+  InvalidCsv { line: usize },
+  InvalidTrace(&'static str),
+  StepLimit(u32),
+  TooManyNeurons,
 }
 
 impl std::fmt::Display for Error {
@@ -15,6 +97,25 @@ impl std::fmt::Display for Error {
       Self::LinkCreate(err) => writeln!(f, "Link creation error: {err}"),
       Self::JoinHandle => writeln!(f, "Join Handle Error"),
       Self::FromInt => writeln!(f, "Could not convert int to float32"),
+      Self::AlreadyRunning => writeln!(f, "Structure can not be changed after simulation start"),
+      Self::UnknownNeuron(id) => writeln!(f, "Neuron {id} is not registered"),
+      Self::TimeStepInPast { requested, current } => writeln!(f, "Time step {requested} is before current time step {current}"),
+      Self::UnknownLink { source, destination } => writeln!(f, "There is no link from {source} to {destination}"),
+      Self::Weights(err) => writeln!(f, "Weights error: {err}"),
+      Self::Population(err) => writeln!(f, "Population error: {err}"),
+      Self::InvalidCurrent(err) => writeln!(f, "Invalid current source: {err}"),
+      Self::SampleShape { expected, found } => writeln!(f, "Sample does not fit the network: expected {expected}, got {found}"),
+      Self::UnknownDirector(index) => writeln!(f, "There is no director {index} in simulation"),
+      Self::DuplicateDirector(id) => writeln!(f, "Director {id} is already registered"),
+      Self::Training(err) => writeln!(f, "Training error: {err}"),
+      Self::Npy(err) => writeln!(f, "Invalid npy file: {err}"),
+      Self::Conversion(err) => writeln!(f, "Conversion error: {err}"),
+      Self::InvalidPlasticity(err) => writeln!(f, "Invalid plasticity rule: {err}"),
+      Self::InvalidCircuit(err) => writeln!(f, "Invalid circuit: {err}"),
+      Self::InvalidCsv { line } => writeln!(f, "Invalid spike CSV at line {line}"),
+      Self::InvalidTrace(err) => writeln!(f, "Invalid trace: {err}"),
+      Self::StepLimit(steps) => writeln!(f, "Condition was not met within {steps} steps"),
+      Self::TooManyNeurons => writeln!(f, "Director has no neuron ids left"),
     }
   }
 }
@@ -27,6 +128,25 @@ impl std::error::Error for Error {
           Error::LinkCreate(_) => None,
           Error::JoinHandle => None,
           Error::FromInt => None,
+          Error::AlreadyRunning => None,
+          Error::UnknownNeuron(_) => None,
+          Error::TimeStepInPast { .. } => None,
+          Error::UnknownLink { .. } => None,
+          Error::Weights(_) => None,
+          Error::Population(_) => None,
+          Error::InvalidCurrent(_) => None,
+          Error::SampleShape { .. } => None,
+          Error::UnknownDirector(_) => None,
+          Error::DuplicateDirector(_) => None,
+          Error::Training(_) => None,
+          Error::Npy(_) => None,
+          Error::Conversion(_) => None,
+          Error::InvalidPlasticity(_) => None,
+          Error::InvalidCircuit(_) => None,
+          Error::InvalidCsv { .. } => None,
+          Error::InvalidTrace(_) => None,
+          Error::StepLimit(_) => None,
+          Error::TooManyNeurons => None,
       }
   }
 }
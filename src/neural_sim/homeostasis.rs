@@ -0,0 +1,236 @@
+use std::collections::{HashMap, HashSet};
+
+use super::error::Error;
+use super::plasticity::{Plasticity, Synapses};
+use super::stimulus::NeuronControl;
+use super::{Director, NeuronUniqueId};
+
+/// Exponential estimate of a firing rate in spikes per step.
+#[derive(Debug, Clone, Copy, Default)]
+struct RateEstimate {
+    rate: f32,
+}
+
+impl RateEstimate {
+    fn update(&mut self, spiked: bool, elapsed: f32, tau: f32) {
+        self.rate *= (-elapsed / tau).exp();
+        if spiked {
+            self.rate += 1. - (-1. / tau).exp();
+        }
+    }
+}
+
+fn elapsed_since(last_step: &mut Option<u32>, time_step: u32) -> f32 {
+    let elapsed = last_step.map_or(1., |last| time_step.saturating_sub(last) as f32);
+    *last_step = Some(time_step);
+    elapsed
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AdaptiveThresholdParams {
+    /// Added to the threshold on every spike.
+    pub increment: f32,
+    /// Time constant in steps of the decay back to the baseline threshold.
+    pub tau: f32,
+    /// Firing rate in spikes per step the baseline threshold is pulled towards, if any.
+    pub target_rate: Option<f32>,
+    /// How far the baseline moves per step and per unit of rate error.
+    pub homeostasis_rate: f32,
+    /// Time constant in steps of the rate estimate.
+    pub tau_rate: f32,
+    pub min_threshold: f32,
+}
+
+impl Default for AdaptiveThresholdParams {
+    fn default() -> Self {
+        Self { increment: 0.05, tau: 50., target_rate: None, homeostasis_rate: 0.01, tau_rate: 1000., min_threshold: 0.01 }
+    }
+}
+
+/// Intrinsic plasticity: each spike raises the threshold of a neuron, which then decays back
+/// to a baseline. With a target rate the baseline itself drifts until the neuron fires at it.
+///
+/// Baselines are taken from the neuron thresholds at the first step the rule sees.
+pub struct AdaptiveThreshold {
+    params: AdaptiveThresholdParams,
+    ids: Vec<NeuronUniqueId>,
+    baselines: Vec<f32>,
+    adaptation: Vec<f32>,
+    rates: Vec<RateEstimate>,
+    last_step: Option<u32>,
+}
+
+impl AdaptiveThreshold {
+    pub fn new(ids: &[NeuronUniqueId], params: AdaptiveThresholdParams) -> Self {
+        Self {
+            params,
+            ids: ids.to_vec(),
+            baselines: Vec::new(),
+            adaptation: vec![0.; ids.len()],
+            rates: vec![RateEstimate::default(); ids.len()],
+            last_step: None,
+        }
+    }
+
+    pub fn baselines(&self) -> &[f32] {
+        &self.baselines
+    }
+
+    /// Estimated firing rate of every neuron, in spikes per step.
+    pub fn rates(&self) -> Vec<f32> {
+        self.rates.iter().map(|estimate| estimate.rate).collect()
+    }
+}
+
+impl Plasticity for AdaptiveThreshold {
+    fn step(
+        &mut self,
+        time_step: u32,
+        fired: &[NeuronUniqueId],
+        _synapses: &mut Synapses,
+        neurons: &mut NeuronControl,
+    ) -> Result<(), Error> {
+        if self.baselines.is_empty() {
+            self.baselines = self.ids.iter().map(|id| neurons.threshold(*id)).collect::<Result<_, _>>()?;
+        }
+        let elapsed = elapsed_since(&mut self.last_step, time_step);
+        let decay = (-elapsed / self.params.tau).exp();
+        let fired: HashSet<NeuronUniqueId> = fired.iter().copied().collect();
+
+        for (index, id) in self.ids.iter().enumerate() {
+            let spiked = fired.contains(id);
+            self.adaptation[index] *= decay;
+            if spiked {
+                self.adaptation[index] += self.params.increment;
+            }
+            self.rates[index].update(spiked, elapsed, self.params.tau_rate);
+            if let Some(target_rate) = self.params.target_rate {
+                let error = self.rates[index].rate - target_rate;
+                self.baselines[index] =
+                    (self.baselines[index] + self.params.homeostasis_rate * error * elapsed).max(self.params.min_threshold);
+            }
+            let threshold = (self.baselines[index] + self.adaptation[index]).max(self.params.min_threshold);
+            neurons.set_threshold(*id, threshold)?;
+        }
+        Ok(())
+    }
+
+    /// Drops the spike-triggered part of the thresholds, learned baselines are kept.
+    fn reset(&mut self, neurons: &mut NeuronControl) -> Result<(), Error> {
+        self.adaptation.fill(0.);
+        self.last_step = None;
+        for (id, baseline) in self.ids.iter().zip(&self.baselines) {
+            neurons.set_threshold(*id, *baseline)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SynapticScalingParams {
+    /// Firing rate in spikes per step every neuron is pushed towards.
+    pub target_rate: f32,
+    /// Relative weight change per scaling for a rate error as large as the target rate.
+    pub scaling_rate: f32,
+    /// Time constant in steps of the rate estimate.
+    pub tau_rate: f32,
+    /// Steps between two scalings.
+    pub interval: u32,
+}
+
+impl Default for SynapticScalingParams {
+    fn default() -> Self {
+        Self { target_rate: 0.05, scaling_rate: 0.1, tau_rate: 1000., interval: 100 }
+    }
+}
+
+/// Multiplies all excitatory incoming weights of a neuron by
+/// `1 + scaling_rate * (target - rate) / target`, keeping their ratios, so a population settles
+/// at the target rate. Inhibitory (negative) weights are left alone, scaling them by the same
+/// factor would make a silent neuron even more inhibited.
+pub struct SynapticScaling {
+    params: SynapticScalingParams,
+    targets: Vec<NeuronUniqueId>,
+    sources: Vec<Vec<NeuronUniqueId>>,
+    index_of: HashMap<NeuronUniqueId, usize>,
+    rates: Vec<RateEstimate>,
+    last_step: Option<u32>,
+    last_scaling: Option<u32>,
+}
+
+impl SynapticScaling {
+    /// Scales the excitatory links incoming to `targets` that exist at this point.
+    pub fn new(director: &Director, targets: &[NeuronUniqueId], params: SynapticScalingParams) -> Result<Self, Error> {
+        if params.target_rate <= 0. {
+            return Err(Error::InvalidPlasticity("target rate must be positive"));
+        }
+        let mut sources = Vec::with_capacity(targets.len());
+        for target in targets {
+            let mut incoming: Vec<NeuronUniqueId> = director
+                .incoming_links(*target)?
+                .iter()
+                .filter(|link| link.weight > 0.)
+                .map(|link| link.source)
+                .collect();
+            incoming.sort_unstable();
+            incoming.dedup();
+            sources.push(incoming);
+        }
+        Ok(Self {
+            params,
+            targets: targets.to_vec(),
+            sources,
+            index_of: targets.iter().enumerate().map(|(index, id)| (*id, index)).collect(),
+            rates: vec![RateEstimate::default(); targets.len()],
+            last_step: None,
+            last_scaling: None,
+        })
+    }
+
+    /// Estimated firing rate of every target, in spikes per step.
+    pub fn rates(&self) -> Vec<f32> {
+        self.rates.iter().map(|estimate| estimate.rate).collect()
+    }
+}
+
+impl Plasticity for SynapticScaling {
+    fn step(
+        &mut self,
+        time_step: u32,
+        fired: &[NeuronUniqueId],
+        synapses: &mut Synapses,
+        _neurons: &mut NeuronControl,
+    ) -> Result<(), Error> {
+        let elapsed = elapsed_since(&mut self.last_step, time_step);
+        let mut spiked = vec![false; self.targets.len()];
+        for id in fired {
+            if let Some(index) = self.index_of.get(id) {
+                spiked[*index] = true;
+            }
+        }
+        for (estimate, spiked) in self.rates.iter_mut().zip(spiked) {
+            estimate.update(spiked, elapsed, self.params.tau_rate);
+        }
+
+        let last_scaling = *self.last_scaling.get_or_insert(time_step);
+        if time_step.saturating_sub(last_scaling) < self.params.interval {
+            return Ok(());
+        }
+        self.last_scaling = Some(time_step);
+        for ((target, sources), estimate) in self.targets.iter().zip(&self.sources).zip(&self.rates) {
+            let error = (self.params.target_rate - estimate.rate) / self.params.target_rate;
+            let factor = (1. + self.params.scaling_rate * error).max(0.);
+            for source in sources {
+                /* parallel links are scaled one by one, an inhibitory one among them stays as it is */
+                synapses.update_weights(*source, *target, |weight| if weight > 0. { weight * factor } else { weight })?;
+            }
+        }
+        Ok(())
+    }
+
+    fn reset(&mut self, _neurons: &mut NeuronControl) -> Result<(), Error> {
+        self.last_step = None;
+        self.last_scaling = None;
+        Ok(())
+    }
+}
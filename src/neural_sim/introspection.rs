@@ -0,0 +1,135 @@
+use super::{Director, Error, NeuronUniqueId, Simulation, local_index};
+
+pub struct NeuronInfo {
+    pub id: NeuronUniqueId,
+    pub kind: &'static str,
+    pub parameters: Vec<(&'static str, f32)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Link {
+    pub source: NeuronUniqueId,
+    pub destination: NeuronUniqueId,
+    pub weight: f32,
+}
+
+/// `histogram[d]` holds the number of neurons with degree `d`.
+fn degree_histogram(degrees: impl Iterator<Item = usize>) -> Vec<usize> {
+    let mut histogram: Vec<usize> = Vec::new();
+    for degree in degrees {
+        if histogram.len() <= degree {
+            histogram.resize(degree + 1, 0);
+        }
+        histogram[degree] += 1;
+    }
+    histogram
+}
+
+impl Director {
+    pub fn neuron_ids(&self) -> &[NeuronUniqueId] {
+        &self.planner.assigned_id_vec
+    }
+
+    pub fn neuron_info(&self, id: NeuronUniqueId) -> Result<NeuronInfo, Error> {
+        if let Some((store, index)) = self.vectorized_slot(id) {
+            let store = &self.vectorized[store];
+            return Ok(NeuronInfo {
+                id,
+                kind: "lif",
+                parameters: vec![("beta", store.betas()[index]), ("threshold", store.thresholds()[index])],
+            });
+        }
+        let neuron = self.id_to_mux_map.get(&id).ok_or(Error::UnknownNeuron(id))?;
+        let lock = neuron.lock()?;
+        Ok(NeuronInfo {
+            id,
+            kind: lock.kind(),
+            parameters: lock.parameters(),
+        })
+    }
+
+    pub fn neurons(&self) -> Result<Vec<NeuronInfo>, Error> {
+        self.neuron_ids()
+            .iter()
+            .map(|id| self.neuron_info(*id))
+            .collect()
+    }
+
+    pub fn outgoing_links(&self, id: NeuronUniqueId) -> Result<Vec<Link>, Error> {
+        if !self.planner.is_booked(id) {
+            return Err(Error::UnknownNeuron(id));
+        }
+        Ok(self
+            .planner
+            .connection_map
+            .get(&id)
+            .map(|pairs| {
+                pairs
+                    .iter()
+                    .map(|pair| Link { source: id, destination: pair.id, weight: pair.weight })
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    pub fn incoming_links(&self, id: NeuronUniqueId) -> Result<Vec<Link>, Error> {
+        if !self.planner.is_booked(id) {
+            return Err(Error::UnknownNeuron(id));
+        }
+        let mut links: Vec<Link> = self
+            .links()
+            .filter(|link| link.destination == id)
+            .collect();
+        links.sort_by_key(|link| link.source);
+        Ok(links)
+    }
+
+    /// Iterates over every link of the director, in no particular order.
+    pub fn links(&self) -> impl Iterator<Item = Link> + '_ {
+        self.planner.connection_map.iter().flat_map(|(source, pairs)| {
+            pairs.iter().map(|pair| Link { source: *source, destination: pair.id, weight: pair.weight })
+        })
+    }
+
+    /// Weight of the first link from `source` to `destination`.
+    pub fn weight(&self, source: NeuronUniqueId, destination: NeuronUniqueId) -> Option<f32> {
+        self.planner
+            .connection_map
+            .get(&source)?
+            .iter()
+            .find(|pair| pair.id == destination)
+            .map(|pair| pair.weight)
+    }
+
+    /// Sets the weight of every link from `source` to `destination`.
+    pub fn set_weight(&mut self, source: NeuronUniqueId, destination: NeuronUniqueId, weight: f32) -> Result<(), Error> {
+        self.planner.set_weight(source, destination, weight)
+    }
+
+    pub fn synapse_count(&self) -> usize {
+        self.planner.connection_map.values().map(Vec::len).sum()
+    }
+
+    pub fn out_degree_histogram(&self) -> Vec<usize> {
+        degree_histogram(self.neuron_ids().iter().map(|id| {
+            self.planner.connection_map.get(id).map_or(0, Vec::len)
+        }))
+    }
+
+    pub fn in_degree_histogram(&self) -> Vec<usize> {
+        let mut in_degrees: Vec<usize> = vec![0; self.neuron_ids().len()];
+        for link in self.links() {
+            /* ids are booked sequentially, so their offset from the first one indexes the vector */
+            if let Some(in_degree) = local_index(self.planner.first_id, link.destination).and_then(|index| in_degrees.get_mut(index)) {
+                *in_degree += 1;
+            }
+        }
+        degree_histogram(in_degrees.into_iter())
+    }
+}
+
+impl Simulation {
+    pub fn synapse_count(&self) -> usize {
+        self.directors().iter().map(Director::synapse_count).sum()
+    }
+}
@@ -1,30 +1,253 @@
-use std::collections::{HashMap, hash_map::Entry::Vacant};
+use std::collections::{BTreeMap, HashMap, HashSet};
 // use std::error::Error;
 use std::fs::File;
 use std::sync::{Arc, Barrier, Mutex, MutexGuard, RwLock, mpsc, mpsc::Receiver, mpsc::Sender};
-use std::thread::{self};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
 
-use neuron::Neuron;
+use neuron::{Neuron, ResetMode};
 use vcd_ng::{IdCode, TimescaleUnit, Writer};
-use error::Error;
+use connectivity::{CsrConnectivity, InputBuffer};
+use current::Injection;
+use error::{Error, LinkCreateError, WeightError};
+use plasticity::Plasticity;
+use population::Population;
+use random::{DEFAULT_SEED, Rng, Stream, derive_seed};
+use stimulus::Stimulus;
+use vectorized::LifPopulationStore;
 
 pub mod neuron;
 pub mod error;
+pub mod analysis;
+pub mod circuit;
+mod connectivity;
+pub mod conversion;
+pub mod current;
+pub mod homeostasis;
+pub mod introspection;
+pub mod plasticity;
+pub mod plot;
+pub mod population;
+pub mod random;
+pub mod readout;
+pub mod stimulus;
+pub mod trace;
+pub mod training;
+pub mod vectorized;
 
-type NeuronUniqueId = u32;
+pub type NeuronUniqueId = u32;
+/// Bits of a neuron id numbering the neurons of one director; the bits above hold the id of
+/// the director that booked it, so ids of different directors never collide.
+const LOCAL_ID_BITS: u32 = 24;
+/// Exclusive upper bound of director ids.
+pub const MAX_DIRECTOR_ID: u32 = 1 << (NeuronUniqueId::BITS - LOCAL_ID_BITS);
+
+/// Position of `id` among the neurons of the director whose ids start at `first_id`, `None`
+/// for ids booked by another director.
+fn local_index(first_id: NeuronUniqueId, id: NeuronUniqueId) -> Option<usize> {
+    (id >> LOCAL_ID_BITS == first_id >> LOCAL_ID_BITS).then_some((id - first_id) as usize)
+}
 type SharedWriter = Arc<Mutex<Writer<File>>>;
 
-#[allow(dead_code)] // Is not used in tests, but should be. //todo
+pub type WeightFn = Box<dyn FnMut(usize, usize) -> f32>;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WeightDistribution {
+    Uniform { low: f32, high: f32 },
+    Normal { mean: f32, std_dev: f32 },
+    LogNormal { mu: f32, sigma: f32 },
+}
+
+/// Weights for `create_links_by_rule`. Indices `(i, j)` are positions in the `sources` and
+/// `destinations` slices, not neuron ids.
 pub enum VecOrValueFloat {
+    /// Dense `sources.len() x destinations.len()` matrix.
     Vec(Vec<Vec<f32>>),
     Val(f32),
+    Fn(WeightFn),
+    /// Drawn independently for every created link, in row-major order.
+    Random { distribution: WeightDistribution, seed: u64 },
+    /// Same as `Random`, seeded from the projection stream of the director.
+    Distribution(WeightDistribution),
+    /// `(i, j, weight)` triplets; pairs that are not listed are not linked.
+    Sparse(Vec<(usize, usize, f32)>),
+}
+
+impl VecOrValueFloat {
+    fn validate(&self, rows: usize, columns: usize) -> Result<(), WeightError> {
+        match self {
+            Self::Vec(matrix) => {
+                if matrix.len() != rows {
+                    return Err(WeightError::RowCount { expected: rows, found: matrix.len() });
+                }
+                if let Some((row, values)) = matrix.iter().enumerate().find(|(_, values)| values.len() != columns) {
+                    return Err(WeightError::ColumnCount { row, expected: columns, found: values.len() });
+                }
+            }
+            Self::Sparse(triplets) => {
+                if let Some((i, j, _)) = triplets.iter().find(|(i, j, _)| *i >= rows || *j >= columns) {
+                    return Err(WeightError::SparseIndex { row: *i, column: *j, rows, columns });
+                }
+            }
+            Self::Random { distribution, .. } | Self::Distribution(distribution) => match distribution {
+                WeightDistribution::Uniform { low, high } if !low.is_finite() || !high.is_finite() => {
+                    return Err(WeightError::InvalidDistribution("uniform bounds are not finite"));
+                }
+                WeightDistribution::Uniform { low, high } if low > high => {
+                    return Err(WeightError::InvalidDistribution("uniform low bound is above high bound"));
+                }
+                WeightDistribution::Normal { mean, std_dev } if !mean.is_finite() || !std_dev.is_finite() => {
+                    return Err(WeightError::InvalidDistribution("normal parameters are not finite"));
+                }
+                WeightDistribution::Normal { std_dev, .. } if *std_dev < 0. => {
+                    return Err(WeightError::InvalidDistribution("normal standard deviation is negative"));
+                }
+                WeightDistribution::LogNormal { mu, sigma } if !mu.is_finite() || !sigma.is_finite() => {
+                    return Err(WeightError::InvalidDistribution("lognormal parameters are not finite"));
+                }
+                WeightDistribution::LogNormal { sigma, .. } if *sigma < 0. => {
+                    return Err(WeightError::InvalidDistribution("lognormal sigma is negative"));
+                }
+                _ => {}
+            },
+            Self::Val(_) | Self::Fn(_) => {}
+        }
+        Ok(())
+    }
+
+    fn into_weight_fn(self, projection_seed: u64) -> WeightFn {
+        match self {
+            Self::Distribution(distribution) => Self::Random { distribution, seed: projection_seed }.into_weight_fn(projection_seed),
+            Self::Vec(matrix) => Box::new(move |i, j| matrix[i][j]),
+            Self::Val(value) => Box::new(move |_, _| value),
+            Self::Fn(func) => func,
+            Self::Random { distribution, seed } => {
+                let mut rng = Rng::seed_from_u64(seed);
+                Box::new(move |_, _| match distribution {
+                    WeightDistribution::Uniform { low, high } => rng.uniform(low, high),
+                    WeightDistribution::Normal { mean, std_dev } => rng.normal(mean, std_dev),
+                    WeightDistribution::LogNormal { mu, sigma } => rng.lognormal(mu, sigma),
+                })
+            }
+            Self::Sparse(_) => unreachable!("sparse weights are linked straight from their triplets"),
+        }
+    }
 }
 
-#[allow(dead_code)] // Is not used in tests, but should be. //todo
+pub type LinkPredicate = Box<dyn FnMut(usize, usize) -> bool>;
+
+/// Selects which `(i, j)` pairs of `sources` and `destinations` positions get linked.
 pub enum BatchLinkingRule {
+    /// Creates no links.
     None,
     FullyConnected,
+    /// Links the i-th source to the i-th destination; both slices must be of the same length.
+    OneToOne,
+    Sparse(Vec<(usize, usize)>),
     UserDefined(fn(usize, usize) -> bool),
+    /// Same as `UserDefined`, but the predicate can capture state.
+    Predicate(LinkPredicate),
+    /// Every pair is linked independently with the given probability in [0, 1], drawn from the projection stream of the director.
+    Probability(f32),
+}
+
+impl BatchLinkingRule {
+    /// Every pair is linked independently with probability `p`.
+    pub fn probability(p: f32, seed: u64) -> Self {
+        let mut rng = Rng::seed_from_u64(seed);
+        Self::Predicate(Box::new(move |_, _| rng.bernoulli(p)))
+    }
+
+    /// Links pairs whose positions are at most `radius` apart.
+    pub fn neighborhood(radius: usize) -> Self {
+        Self::Predicate(Box::new(move |i, j| i.abs_diff(j) <= radius))
+    }
+
+    fn validate(&self, rows: usize, columns: usize) -> Result<(), LinkCreateError> {
+        match self {
+            Self::OneToOne if rows != columns => {
+                Err(LinkCreateError::LengthMismatch { sources: rows, destinations: columns })
+            }
+            Self::Sparse(pairs) => match pairs.iter().find(|(i, j)| *i >= rows || *j >= columns) {
+                Some((i, j)) => Err(LinkCreateError::RuleIndex { row: *i, column: *j }),
+                None => Ok(()),
+            },
+            Self::Probability(p) if !(0. ..=1.).contains(p) => Err(LinkCreateError::Probability(*p)),
+            _ => Ok(()),
+        }
+    }
+
+    fn into_predicate(self, projection_seed: u64) -> LinkPredicate {
+        match self {
+            Self::Probability(p) => Self::probability(p, projection_seed).into_predicate(projection_seed),
+            Self::None => Box::new(|_, _| false),
+            Self::FullyConnected => Box::new(|_, _| true),
+            Self::OneToOne => Box::new(|i, j| i == j),
+            Self::Sparse(pairs) => {
+                let pairs: HashSet<(usize, usize)> = pairs.into_iter().collect();
+                Box::new(move |i, j| pairs.contains(&(i, j)))
+            }
+            Self::UserDefined(func) => Box::new(func),
+            Self::Predicate(predicate) => predicate,
+        }
+    }
+
+    /// Calls `link` for every selected pair, without walking the whole matrix for one-to-one and sparse rules.
+    fn for_each_pair<F>(self, rows: usize, columns: usize, projection_seed: u64, mut link: F) -> Result<(), Error>
+    where
+        F: FnMut(usize, usize) -> Result<(), Error>,
+    {
+        match self {
+            Self::None => {}
+            Self::OneToOne => {
+                for i in 0..rows {
+                    link(i, i)?;
+                }
+            }
+            Self::Sparse(pairs) => {
+                for (i, j) in pairs {
+                    link(i, j)?;
+                }
+            }
+            rule => {
+                let mut predicate = rule.into_predicate(projection_seed);
+                for i in 0..rows {
+                    for j in 0..columns {
+                        if predicate(i, j) {
+                            link(i, j)?;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What `create_link` does when source and destination are the same neuron.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SelfConnectionPolicy {
+    #[default]
+    Allow,
+    /// Silently drop the link, handy for rules applied from a population onto itself.
+    Skip,
+    Reject,
+}
+
+/// What `create_link` does when the source is already linked to the destination.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MultapsePolicy {
+    #[default]
+    Allow,
+    /// Add the new weight to the existing link instead of creating a second one.
+    Merge,
+    Reject,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LinkPolicy {
+    pub self_connections: SelfConnectionPolicy,
+    pub multapses: MultapsePolicy,
 }
 
 pub trait ControllingUnit {
@@ -35,11 +258,13 @@ pub trait ControllingUnit {
 
     fn init_planned(&mut self, writer_ref: Option<SharedWriter>) -> Result<(), Error>;
     fn start_planned(&mut self) -> Result<(), Error>;
+    fn step_planned(&mut self) -> Result<(), Error>;
     fn increment_time(&mut self);
     fn spawn_neuron_thread_closure(
         neuron_copy: Arc<Mutex<dyn Neuron>>,
         cur_time_clone: Arc<RwLock<u32>>,
         barrier_clone: Arc<Barrier>,
+        stop_flag: Arc<AtomicBool>,
         tx: Sender<u32>,
         writer: Option<SharedWriter>,
         wire: Option<IdCode>,
@@ -62,76 +287,158 @@ struct NeuronIdWeightPair {
 type ForwardOneToManyConnection = Vec<NeuronIdWeightPair>;
 
 struct NeuronRegistrator {
+    first_id: NeuronUniqueId,
     next_available_id: NeuronUniqueId,
     assigned_id_vec: Vec<NeuronUniqueId>,
     connection_map: HashMap<NeuronUniqueId, ForwardOneToManyConnection>,
+    /// Runtime copy of `connection_map`, rebuilt lazily after links are added.
+    frozen: Option<CsrConnectivity>,
 }
 
 impl NeuronRegistrator {
-    fn book_id(&mut self) -> NeuronUniqueId {
+    fn book_id(&mut self) -> Result<NeuronUniqueId, Error> {
         let ret = self.next_available_id;
+        if local_index(self.first_id, ret).is_none() {
+            return Err(Error::TooManyNeurons);
+        }
         self.assigned_id_vec.push(self.next_available_id);
         self.next_available_id += 1;
-        ret
+        Ok(ret)
     }
-    fn new() -> Self {
+    fn new(director_id: u32) -> Self {
+        let first_id = director_id << LOCAL_ID_BITS;
         Self {
-            next_available_id: 0,
+            first_id,
+            next_available_id: first_id,
             assigned_id_vec: Vec::new(),
             connection_map: HashMap::new(),
+            frozen: None,
         }
     }
 
-    fn fire_from_id(
-        &mut self,
-        caller_id: NeuronUniqueId,
-        map_ref: &mut HashMap<NeuronUniqueId, Arc<Mutex<dyn Neuron>>>,
-        time_step: u32,
-    ) -> Result<(), Error> {
-        println!("firing from {caller_id}!");
-        let reicevers_list: &mut ForwardOneToManyConnection =
-            match self.connection_map.get_mut(&caller_id) {
-                Some(val) => val,
-                None => &mut Vec::<NeuronIdWeightPair>::new(),
-            };
-        for recvr_id_weight_pair in reicevers_list {
-            let recv_id = recvr_id_weight_pair.id;
-            let synapse_weight = recvr_id_weight_pair.weight;
-            if let Some(mux) = map_ref.get_mut(&recv_id) {
-                mux.clone().lock()?.recieve_signal(time_step, synapse_weight);
-            };
+    fn freeze(&mut self) -> &CsrConnectivity {
+        let neuron_count = (self.next_available_id - self.first_id) as usize;
+        self.frozen
+            .get_or_insert_with(|| CsrConnectivity::from_map(&self.connection_map, self.first_id, neuron_count))
+    }
+
+    fn fire_from_id(&mut self, caller_id: NeuronUniqueId, input: &mut InputBuffer) {
+        let (targets, weights) = self.freeze().row(caller_id);
+        for (target, weight) in targets.iter().zip(weights) {
+            input.add(*target, *weight);
+        }
+    }
+
+    fn set_weight(&mut self, source: NeuronUniqueId, destination: NeuronUniqueId, weight: f32) -> Result<(), Error> {
+        self.update_weights(source, destination, |_| weight)
+    }
+
+    /// Replaces the weight of every link from `source` to `destination` by `update` of it,
+    /// link by link, so parallel links keep their own weights.
+    fn update_weights<F>(&mut self, source: NeuronUniqueId, destination: NeuronUniqueId, mut update: F) -> Result<(), Error>
+    where
+        F: FnMut(f32) -> f32,
+    {
+        let mut found = false;
+        let pairs = self.connection_map.get_mut(&source).map(|pairs| pairs.iter_mut()).into_iter().flatten();
+        for (position, pair) in pairs.enumerate().filter(|(_, pair)| pair.id == destination) {
+            pair.weight = update(pair.weight);
+            if let Some(frozen) = self.frozen.as_mut() {
+                frozen.set_weight_at(source, position, pair.weight);
+            }
+            found = true;
+        }
+        if !found {
+            return Err(Error::UnknownLink { source, destination });
         }
         Ok(())
     }
 
-    fn link(&mut self, source_id: NeuronUniqueId, dest_id: NeuronUniqueId, weight: f32) -> Result<(), Error> {
-        let added_pair = NeuronIdWeightPair {
+    /// Whether `id` was booked here; ids of other directors never are.
+    fn is_booked(&self, id: NeuronUniqueId) -> bool {
+        (self.first_id..self.next_available_id).contains(&id)
+    }
+
+    fn link(&mut self, source_id: NeuronUniqueId, dest_id: NeuronUniqueId, weight: f32, policy: LinkPolicy) -> Result<(), Error> {
+        if !self.is_booked(source_id) {
+            return Err(Error::LinkCreate(LinkCreateError::UnknownSource(source_id)));
+        }
+        if !self.is_booked(dest_id) {
+            return Err(Error::LinkCreate(LinkCreateError::UnknownDestination(dest_id)));
+        }
+        if source_id == dest_id {
+            match policy.self_connections {
+                SelfConnectionPolicy::Allow => {}
+                SelfConnectionPolicy::Skip => return Ok(()),
+                SelfConnectionPolicy::Reject => {
+                    return Err(Error::LinkCreate(LinkCreateError::SelfConnection(source_id)));
+                }
+            }
+        }
+
+        self.frozen = None;
+        let connections = self.connection_map.entry(source_id).or_default();
+        if let Some(existing) = connections.iter_mut().find(|pair| pair.id == dest_id) {
+            match policy.multapses {
+                MultapsePolicy::Allow => {}
+                MultapsePolicy::Merge => {
+                    existing.weight += weight;
+                    return Ok(());
+                }
+                MultapsePolicy::Reject => {
+                    return Err(Error::LinkCreate(LinkCreateError::Multapse {
+                        source: source_id,
+                        destination: dest_id,
+                    }));
+                }
+            }
+        }
+        connections.push(NeuronIdWeightPair {
             id: dest_id,
             weight,
-        };
-        if let Vacant(e) = self.connection_map.entry(source_id) {
-            e.insert(vec![added_pair]);
-        } else if let Some(map_ref) = self.connection_map.get_mut(&source_id){
-            map_ref.push(added_pair);
-        } else {
-            return Err(Error::LinkCreate("w"));
-        }
+        });
         Ok(())
     }
 }
 
+/// Where the state of a neuron lives, indexed by id once the director is initialized.
+enum NeuronSlot {
+    Threaded(Arc<Mutex<dyn Neuron>>),
+    Vectorized { store: usize, index: usize },
+}
+
 pub struct Director {
     subordinates: Vec<Arc<Mutex<dyn Neuron>>>,
     sim_time: u32,
     cur_time: u32,
     planner: NeuronRegistrator,
     id_to_mux_map: HashMap<NeuronUniqueId, Arc<Mutex<dyn Neuron>>>,
-    // id: u32,
+    id: u32,
     name: String,
+    link_policy: LinkPolicy,
+    seed: u64,
+    projection_count: u64,
     rx: Option<Receiver<u32>>,
     main_thread_barrier: Option<Arc<Barrier>>,
     cur_time_arc: Option<Arc<RwLock<u32>>>,
     writer_ref: Option<SharedWriter>,
+    /// One-bit trace wires that are high at the steps a neuron fired.
+    spike_wires: HashMap<NeuronUniqueId, IdCode>,
+    raised_spike_wires: Vec<IdCode>,
+    thread_handles: Vec<JoinHandle<()>>,
+    stop_flag: Arc<AtomicBool>,
+    initialized: bool,
+    fired_last_step: Vec<NeuronUniqueId>,
+    populations: Vec<Population>,
+    vectorized: Vec<LifPopulationStore>,
+    slots: Vec<NeuronSlot>,
+    input_buffer: InputBuffer,
+    injections: Vec<Injection>,
+    stimuli: BTreeMap<u32, Vec<Stimulus>>,
+    recorded_ids: HashSet<NeuronUniqueId>,
+    spike_record: Vec<(NeuronUniqueId, u32)>,
+    potential_record: BTreeMap<NeuronUniqueId, Vec<(u32, f32)>>,
+    plasticity: Vec<Box<dyn Plasticity>>,
 }
 
 impl ControllingUnit for Director {
@@ -139,7 +446,10 @@ impl ControllingUnit for Director {
         &mut self,
         added_subordinate: Arc<Mutex<dyn Neuron>>,
     ) -> Result<NeuronUniqueId, Error> {
-        let id = self.planner.book_id();
+        if self.initialized {
+            return Err(Error::AlreadyRunning);
+        }
+        let id = self.planner.book_id()?;
         let trait_clone = Arc::clone(&added_subordinate);
 
         match thread::spawn(move || {
@@ -164,6 +474,7 @@ impl ControllingUnit for Director {
         neuron_copy: Arc<Mutex<dyn Neuron>>,
         cur_time_clone: Arc<RwLock<u32>>,
         barrier_clone: Arc<Barrier>,
+        stop_flag: Arc<AtomicBool>,
         tx: Sender<u32>,
         writer: Option<SharedWriter>,
         wire: Option<IdCode>,
@@ -180,7 +491,6 @@ impl ControllingUnit for Director {
                     if let Some(v) = wr {
                         v.lock()?.change_real(wi.unwrap(), lock.get_signal().into())?; // See comment above
                     }
-                    println!("{}", lock.get_signal());
                     Ok(())
                 };
 
@@ -189,7 +499,6 @@ impl ControllingUnit for Director {
                 lock.init();
             }
 
-            let mut cur_time = *cur_time_clone.read().unwrap(); // See comment above
             barrier_clone.wait(); // sync with blocked threads to upscope at a right moment
             barrier_clone.wait(); // sync after upscope to define default values            
             {
@@ -202,6 +511,11 @@ impl ControllingUnit for Director {
             loop {
                 // main neuron loop
                 barrier_clone.wait(); // sync before concurrent execution
+                if stop_flag.load(Ordering::Acquire) {
+                    break;
+                }
+                /* read every step, the director may rewind time between steps */
+                let cur_time = *cur_time_clone.read().unwrap(); // See comment above
                 {
                     let mut lock = neuron_copy.lock().unwrap(); // See comment above
 
@@ -209,7 +523,6 @@ impl ControllingUnit for Director {
                     write_cur_signal(&writer, &wire, &mut lock).unwrap(); // See comment above
                     /* in this interval, neurons compute, fire, receive signals */
                     while lock.get_earliest_event_available().unwrap() {
-                        println!("checking at time: {}", cur_time);
                         if *lock.get_earliest_event().unwrap() == cur_time {
                             let fired_id = lock.fire().unwrap();
                             lock.pop_earliest_event();
@@ -222,20 +535,46 @@ impl ControllingUnit for Director {
                 }
                 barrier_clone.wait(); // sync before time increment
                 barrier_clone.wait(); // sync after time increment to sync current time
-                cur_time = *cur_time_clone.read().unwrap();
             }
         };
         Ok(closure)
     }
 
     fn init_planned(&mut self, writer_ref: Option<SharedWriter>) -> Result<(), Error> {
+        if self.initialized {
+            return Err(Error::AlreadyRunning);
+        }
         let mut thread_handles = Vec::new();
         let cur_time_arc = Arc::new(RwLock::new(self.cur_time));
         let timestep_barrier = Arc::new(Barrier::new(self.subordinates.len() + 1));
         let (tx, rx) = mpsc::channel::<u32>();
 
+        let mut population_wires: HashMap<NeuronUniqueId, IdCode> = HashMap::new();
         if let Some(ref writer) = writer_ref {
-            writer.lock()?.add_module(&self.name)?;
+            let mut writer_lock = writer.lock()?;
+            writer_lock.add_module(&self.name)?;
+            for population in &self.populations {
+                writer_lock.add_module(&population.trace_scope_name())?;
+                for id in population.iter() {
+                    let wire = writer_lock.add_var(
+                        vcd_ng::VarType::Real,
+                        size_of::<f32>().try_into()?,
+                        &id.to_string(),
+                        None,
+                    )?;
+                    population_wires.insert(*id, wire);
+                    let spike_wire = writer_lock.add_var(vcd_ng::VarType::Wire, 1, &format!("{id}_spike"), None)?;
+                    self.spike_wires.insert(*id, spike_wire);
+                }
+                writer_lock.upscope()?;
+            }
+        }
+        for store in &mut self.vectorized {
+            for index in 0..store.len() {
+                if let Some(wire) = population_wires.get(&store.id_of(index)) {
+                    store.set_wire(index, *wire);
+                }
+            }
         }
 
         for subord_trait in &self.subordinates {
@@ -248,7 +587,9 @@ impl ControllingUnit for Director {
                     let neuron_lock = self_copy.lock()?;
                     let mut writer_lock = value.lock()?;
 
-                    if let Some(id) = neuron_lock.get_id() {
+                    if let Some(wire) = neuron_lock.get_id().and_then(|id| population_wires.get(&id)) {
+                        Some(*wire)
+                    } else if let Some(id) = neuron_lock.get_id() {
                         let wire = writer_lock
                             .add_var(
                                 vcd_ng::VarType::Real,
@@ -256,6 +597,8 @@ impl ControllingUnit for Director {
                                 &id.to_string(),
                                 None,
                             )?;
+                        let spike_wire = writer_lock.add_var(vcd_ng::VarType::Wire, 1, &format!("{id}_spike"), None)?;
+                        self.spike_wires.insert(id, spike_wire);
                         Some(wire)
                     } else {None}
                 }
@@ -266,6 +609,7 @@ impl ControllingUnit for Director {
                 self_copy,
                 cur_time_clone,
                 barrier_clone,
+                Arc::clone(&self.stop_flag),
                 tx.clone(),
                 writer_ref.as_ref().map(Arc::clone),
                 wire,
@@ -289,11 +633,26 @@ impl ControllingUnit for Director {
         //     let _ = writer_mux.lock().unwrap().end();
         // }
 
+        self.slots = Vec::with_capacity(self.planner.assigned_id_vec.len());
+        for id in &self.planner.assigned_id_vec {
+            let slot = match self.id_to_mux_map.get(id) {
+                Some(neuron) => NeuronSlot::Threaded(Arc::clone(neuron)),
+                None => {
+                    let (store, index) = self.vectorized_slot(*id).ok_or(Error::UnknownNeuron(*id))?;
+                    NeuronSlot::Vectorized { store, index }
+                }
+            };
+            self.slots.push(slot);
+        }
+        self.planner.freeze();
+
         self.main_thread_barrier = Some(Arc::clone(&timestep_barrier));
         self.writer_ref = writer_ref;
         self.rx = Some(rx);
         self.cur_time_arc = Some(cur_time_arc);
-        
+        self.thread_handles = thread_handles;
+        self.initialized = true;
+
         Ok(())
     }
 
@@ -308,6 +667,15 @@ impl ControllingUnit for Director {
         }
 
         wait_func(self); // sync after upscope, before default vars definition        
+        for store in &self.vectorized {
+            store.write_trace(&self.writer_ref)?;
+        }
+        if let Some(ref writer_mux) = self.writer_ref {
+            let mut lock = writer_mux.lock()?;
+            for wire in self.spike_wires.values() {
+                lock.change_scalar(*wire, vcd_ng::Value::V0)?;
+            }
+        }
         wait_func(self); // sync before any actions
         if let Some(ref writer_mux) = self.writer_ref {
             writer_mux.lock()?.end()?;
@@ -315,40 +683,102 @@ impl ControllingUnit for Director {
         wait_func(self); // sync before any actions
         // self.writer_ref.as_ref().inspect(|v| {if let Ok(mut v) = v.lock() { let _ = v.enddefinitions(); }});
 
-        while self.cur_time != self.sim_time {
+        Ok(())
+    }
+
+    /// Runs delta cycles until nothing fires: every cycle collects the spikes fired so far,
+    /// sums their input per target in the `InputBuffer` and delivers it, which may fire more
+    /// neurons at the same step. Time advances once a cycle ends without spikes.
+    fn step_planned(&mut self) -> Result<(), Error> {
+        let wait_func = |s: &mut Self| if let Some(barier) = s.main_thread_barrier.as_ref(){
+            barier.wait();
+        };
+
+        self.fired_last_step.clear();
+        if let Some(ref writer_mux) = self.writer_ref {
+            let mut lock = writer_mux.lock()?;
+            for wire in self.raised_spike_wires.drain(..) {
+                lock.change_scalar(wire, vcd_ng::Value::V0)?;
+            }
+        }
+        let time_step = self.cur_time;
+        let mut first_delta = true;
+        loop {
             let mut none_neurons_have_fired: bool = true;
 
             wait_func(self);
+            /* neuron threads run concurrently with the vectorized populations here */
+            let mut vectorized_fired: Vec<NeuronUniqueId> = Vec::new();
+            for store in &mut self.vectorized {
+                store.leak(self.cur_time);
+                store.write_trace(&self.writer_ref)?;
+                store.collect_fired(self.cur_time, &mut vectorized_fired);
+            }
             wait_func(self);
 
             /* after this, all neurons await barrier in new inputs and do not hold lock */
-            for sender_id in self.rx.as_mut().unwrap().try_iter() {
+            let mut fired: Vec<NeuronUniqueId> = match self.rx.as_mut() {
+                Some(rx) => rx.try_iter().collect(),
+                None => Vec::new(),
+            };
+            fired.extend(vectorized_fired);
+            for sender_id in fired {
                 none_neurons_have_fired = false;
-
-                println!("emmit request got from {sender_id}");
-                self.planner
-                    .fire_from_id(sender_id, &mut self.id_to_mux_map, self.cur_time)?;
+                self.planner.fire_from_id(sender_id, &mut self.input_buffer);
+                self.fired_last_step.push(sender_id);
+                if let (Some(writer_mux), Some(wire)) = (self.writer_ref.as_ref(), self.spike_wires.get(&sender_id)) {
+                    writer_mux.lock()?.change_scalar(*wire, vcd_ng::Value::V1)?;
+                    self.raised_spike_wires.push(*wire);
+                }
+                if self.recorded_ids.contains(&sender_id) {
+                    self.spike_record.push((sender_id, self.cur_time));
+                }
+            }
+            if first_delta {
+                /* stimuli and injected currents arrive once per step, after leak, together with the first synaptic input */
+                if self.apply_stimuli()? {
+                    none_neurons_have_fired = false;
+                }
+                for injection in &mut self.injections {
+                    let current = injection.sample(self.cur_time);
+                    if current != 0. {
+                        self.input_buffer.add(injection.target(), current);
+                    }
+                }
+                first_delta = false;
             }
+            /* a target pushed over threshold fires at this step, so it needs one more delta cycle */
+            if !self.input_buffer.is_empty() {
+                none_neurons_have_fired = false;
+            }
+            self.deliver_input()?;
 
             if none_neurons_have_fired {
-                println!("a step {} passed of {}\n\n", self.cur_time, self.sim_time);
-
                 self.increment_time();
-                *self.cur_time_arc.as_ref().unwrap().write().unwrap() = self.cur_time;
-                if let Some(ref writer) = self.writer_ref {
-                    let _ = writer.lock().unwrap().timestamp(self.cur_time.into());
+                if let Some(cur_time_arc) = self.cur_time_arc.as_ref() {
+                    *cur_time_arc.write()? = self.cur_time;
                 }
             }
 
             wait_func(self);
+
+            if none_neurons_have_fired {
+                break;
+            }
         }
-        
-        Ok(())
+
+        let recorded: Vec<NeuronUniqueId> = self.potential_record.keys().copied().collect();
+        for id in recorded {
+            let potential = self.potential(id)?;
+            self.potential_record.entry(id).or_default().push((time_step, potential));
+        }
+
+        self.apply_plasticity(time_step)
     }
 
     fn create_link(&mut self, source: NeuronUniqueId, destination: NeuronUniqueId, weight: f32) -> Result<(), Error> {
         // self.tmp_source_dest_pairs.push([source, destination]);
-        self.planner.link(source, destination, weight)?;
+        self.planner.link(source, destination, weight, self.link_policy)?;
         Ok(())
     }
 
@@ -359,50 +789,254 @@ impl ControllingUnit for Director {
         weights: VecOrValueFloat,
         rule: BatchLinkingRule,
     ) -> Result<(), Error> {
-        let linking_rule: fn(usize, usize) -> bool = match &rule {
-            BatchLinkingRule::None => |_x: usize, _y: usize| true,
-            BatchLinkingRule::FullyConnected => |_x: usize, _y: usize| true,
-            BatchLinkingRule::UserDefined(func) => *func,
-        };
+        rule.validate(sources.len(), destinations.len()).map_err(Error::LinkCreate)?;
+        weights.validate(sources.len(), destinations.len()).map_err(Error::Weights)?;
 
-        let weights = match weights {
-            VecOrValueFloat::Vec(x) => x,
-            VecOrValueFloat::Val(x) => vec![vec![x; destinations.len()]; sources.len()],
-        };
+        let projection_seed = derive_seed(self.seed, Stream::Projection(self.projection_count));
+        let rule_seed = derive_seed(projection_seed, Stream::Sub(0));
+        let weights_seed = derive_seed(projection_seed, Stream::Sub(1));
+        self.projection_count += 1;
 
-        for (i, source_id) in sources.iter().enumerate() {
-            for (j, destination_id) in destinations.iter().enumerate() {
+        if let VecOrValueFloat::Sparse(triplets) = weights {
+            let mut linking_rule = rule.into_predicate(rule_seed);
+            for (i, j, weight) in triplets {
                 if linking_rule(i, j) {
-                    self.create_link(*source_id, *destination_id, weights[i][j])?;
+                    self.create_link(sources[i], destinations[j], weight)?;
                 }
             }
+            return Ok(());
         }
-        Ok(())
+
+        let mut weight_at = weights.into_weight_fn(weights_seed);
+        rule.for_each_pair(sources.len(), destinations.len(), rule_seed, |i, j| {
+            self.create_link(sources[i], destinations[j], weight_at(i, j))
+        })
     }
 }
 
 impl Director {
+    /// `None` if `id` is not below `MAX_DIRECTOR_ID`; the id is part of every neuron id booked here.
     pub fn new(sim_time: u32, id: u32) -> Option<Self> {
+        if id >= MAX_DIRECTOR_ID {
+            return None;
+        }
         Some(Self {
             subordinates: vec![],
             sim_time,
             cur_time: 0,
-            planner: NeuronRegistrator::new(),
+            planner: NeuronRegistrator::new(id),
             id_to_mux_map: HashMap::new(),
-            // id,
+            id,
             name: id.to_string(),
+            link_policy: LinkPolicy::default(),
+            seed: derive_seed(DEFAULT_SEED, Stream::Director(id as usize)),
+            projection_count: 0,
             rx: None,
             main_thread_barrier: None,
             cur_time_arc: None,
             writer_ref: None,
+            spike_wires: HashMap::new(),
+            raised_spike_wires: Vec::new(),
+            thread_handles: Vec::new(),
+            stop_flag: Arc::new(AtomicBool::new(false)),
+            initialized: false,
+            fired_last_step: Vec::new(),
+            populations: Vec::new(),
+            vectorized: Vec::new(),
+            slots: Vec::new(),
+            input_buffer: InputBuffer::new(id << LOCAL_ID_BITS),
+            injections: Vec::new(),
+            stimuli: BTreeMap::new(),
+            recorded_ids: HashSet::new(),
+            spike_record: Vec::new(),
+            potential_record: BTreeMap::new(),
+            plasticity: Vec::new(),
         })
         // sim.register_director(dir)
     }
+
+    /// Policy applied by `create_link` and `create_links_by_rule` to links created afterwards.
+    pub fn set_link_policy(&mut self, policy: LinkPolicy) {
+        self.link_policy = policy;
+    }
+
+    pub fn link_policy(&self) -> LinkPolicy {
+        self.link_policy
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Seeds every random stream of the director. `Simulation::register_director` derives it from the master seed.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    /// Random stream of neuron `id`, independent of every other neuron and projection.
+    pub fn neuron_rng(&self, id: NeuronUniqueId) -> Rng {
+        Rng::seed_from_u64(derive_seed(self.seed, Stream::Neuron(id)))
+    }
+
+    pub fn cur_time(&self) -> u32 {
+        self.cur_time
+    }
+
+    pub fn sim_time(&self) -> u32 {
+        self.sim_time
+    }
+
+    /// Whether the director has reached the `sim_time` it was created with.
+    pub fn is_finished(&self) -> bool {
+        self.cur_time >= self.sim_time
+    }
+
+    /// Ids of the neurons that fired during the last completed time step, in firing order.
+    pub fn fired_last_step(&self) -> &[NeuronUniqueId] {
+        &self.fired_last_step
+    }
+
+    pub fn potential(&self, id: NeuronUniqueId) -> Result<f32, Error> {
+        if let Some((store, index)) = self.vectorized_slot(id) {
+            return Ok(self.vectorized[store].potentials()[index]);
+        }
+        let neuron = self.id_to_mux_map.get(&id).ok_or(Error::UnknownNeuron(id))?;
+        Ok(neuron.lock()?.get_signal())
+    }
+
+    /// Plans a spike of neuron `id` at `time_step`. Can be used between `Simulation::step` calls
+    /// to inject new stimuli into a running network.
+    pub fn schedule_spike(&mut self, id: NeuronUniqueId, time_step: u32) -> Result<(), Error> {
+        if time_step < self.cur_time {
+            return Err(Error::TimeStepInPast { requested: time_step, current: self.cur_time });
+        }
+        if let Some((store, index)) = self.vectorized_slot(id) {
+            self.vectorized[store].schedule(index, time_step);
+            return Ok(());
+        }
+        let neuron = self.id_to_mux_map.get(&id).ok_or(Error::UnknownNeuron(id))?;
+        neuron.lock()?.emmit_signal(time_step);
+        Ok(())
+    }
+
+    /// Hands every target its input summed over the delta cycle, locking threaded neurons once.
+    fn deliver_input(&mut self) -> Result<(), Error> {
+        for (target, signal) in self.input_buffer.drain() {
+            match local_index(self.planner.first_id, target).and_then(|index| self.slots.get(index)) {
+                Some(NeuronSlot::Threaded(neuron)) => neuron.lock()?.recieve_signal(self.cur_time, signal),
+                Some(NeuronSlot::Vectorized { store, index }) => self.vectorized[*store].receive(*index, signal),
+                None => return Err(Error::UnknownNeuron(target)),
+            }
+        }
+        for store in &mut self.vectorized {
+            store.check_thresholds(self.cur_time);
+        }
+        Ok(())
+    }
+
+    /// Clears dynamic state of every neuron while keeping topology and weights, see `ResetMode`.
+    pub fn reset(&mut self, mode: ResetMode) -> Result<(), Error> {
+        if mode == ResetMode::Full {
+            self.cur_time = 0;
+            if let Some(cur_time_arc) = self.cur_time_arc.as_ref() {
+                *cur_time_arc.write()? = 0;
+            }
+            self.spike_record.clear();
+            self.potential_record.values_mut().for_each(Vec::clear);
+        }
+        for neuron in &self.subordinates {
+            let mut lock = neuron.lock()?;
+            lock.reset(mode, self.cur_time);
+            /* neuron threads call init when they start, so only a running director plans again */
+            if mode == ResetMode::Full && self.initialized {
+                lock.init();
+            }
+        }
+        for store in &mut self.vectorized {
+            store.reset(mode, self.cur_time);
+        }
+        if mode != ResetMode::Potentials {
+            self.stimuli.clear();
+            for injection in &mut self.injections {
+                injection.reset();
+            }
+            self.reset_plasticity()?;
+            self.fired_last_step.clear();
+        }
+        Ok(())
+    }
+
+    /// Plans spikes of every neuron in `ids` (a population or a slice of one) at each of `time_steps`.
+    pub fn schedule_spikes(&mut self, ids: &[NeuronUniqueId], time_steps: &[u32]) -> Result<(), Error> {
+        for id in ids {
+            for time_step in time_steps {
+                self.schedule_spike(*id, *time_step)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Starts recording spikes of `ids`; recorded spikes are kept as `(id, time_step)` pairs.
+    pub fn record_spikes(&mut self, ids: &[NeuronUniqueId]) -> Result<(), Error> {
+        if let Some(id) = ids.iter().find(|id| !self.planner.is_booked(**id)) {
+            return Err(Error::UnknownNeuron(*id));
+        }
+        self.recorded_ids.extend(ids);
+        Ok(())
+    }
+
+    pub fn recorded_spikes(&self) -> &[(NeuronUniqueId, u32)] {
+        &self.spike_record
+    }
+
+    pub fn recorded_spikes_of(&self, ids: &[NeuronUniqueId]) -> Vec<(NeuronUniqueId, u32)> {
+        self.spike_record
+            .iter()
+            .filter(|(id, _)| ids.contains(id))
+            .copied()
+            .collect()
+    }
+
+    /// Starts recording the membrane potential of `ids` at the end of every step, after spikes
+    /// and resets of that step.
+    pub fn record_potentials(&mut self, ids: &[NeuronUniqueId]) -> Result<(), Error> {
+        if let Some(id) = ids.iter().find(|id| !self.planner.is_booked(**id)) {
+            return Err(Error::UnknownNeuron(*id));
+        }
+        for id in ids {
+            self.potential_record.entry(*id).or_default();
+        }
+        Ok(())
+    }
+
+    /// `(time_step, potential)` series of every neuron recorded with `record_potentials`.
+    pub fn recorded_potentials(&self) -> &BTreeMap<NeuronUniqueId, Vec<(u32, f32)>> {
+        &self.potential_record
+    }
+}
+
+impl Drop for Director {
+    fn drop(&mut self) {
+        /* neuron threads idle on the barrier between steps; release them once so they can see the stop flag */
+        if !self.initialized || self.thread_handles.iter().any(|handle| handle.is_finished()) {
+            return;
+        }
+        self.stop_flag.store(true, Ordering::Release);
+        if let Some(barrier) = self.main_thread_barrier.as_ref() {
+            barrier.wait();
+        }
+        for handle in self.thread_handles.drain(..) {
+            let _ = handle.join();
+        }
+    }
 }
 
 pub struct Simulation {
     controlled_directors: Vec<Director>,
     trace_writer: Option<SharedWriter>,
+    initialized: bool,
+    elapsed_steps: u32,
+    seed: u64,
 }
 
 impl Simulation {
@@ -424,39 +1058,136 @@ impl Simulation {
         Ok(Self {
             controlled_directors: Vec::new(),
             trace_writer: writer,
+            initialized: false,
+            elapsed_steps: 0,
+            seed: DEFAULT_SEED,
         })
     }
-    pub fn register_director(&mut self, director: Director) -> Option<&mut Director> {
+    /// Directors can only be registered before the first step, later ones would never be
+    /// initialized; director ids must be unique.
+    pub fn register_director(&mut self, mut director: Director) -> Result<&mut Director, Error> {
+        if self.initialized {
+            return Err(Error::AlreadyRunning);
+        }
+        if self.controlled_directors.iter().any(|registered| registered.id == director.id) {
+            return Err(Error::DuplicateDirector(director.id));
+        }
+        director.set_seed(derive_seed(self.seed, Stream::Director(director.id as usize)));
         self.controlled_directors.push(director);
-        self.controlled_directors.last_mut()
+        Ok(self.controlled_directors.last_mut().unwrap())
+    }
+
+    /// Master seed every director, neuron and projection stream is derived from.
+    /// Set it before building the network, links drawn earlier keep their old streams.
+    /// There is no checkpoint format yet; the seed is written to the header of the trace,
+    /// which is what a run has to be reproduced from.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+        for director in &mut self.controlled_directors {
+            director.set_seed(derive_seed(seed, Stream::Director(director.id as usize)));
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn directors(&self) -> &[Director] {
+        &self.controlled_directors
     }
 
-    pub fn start(&mut self) -> Result<(), Error> { 
-        let mut director_refs: Vec<Arc<Mutex<Director>>> = Vec::new();
-        while let Some(director) = self.controlled_directors.pop() {
+    pub fn director_mut(&mut self, index: usize) -> Option<&mut Director> {
+        self.controlled_directors.get_mut(index)
+    }
+
+    /// Number of steps performed since the simulation was started.
+    pub fn elapsed_steps(&self) -> u32 {
+        self.elapsed_steps
+    }
+
+    fn ensure_initialized(&mut self) -> Result<(), Error> {
+        if self.initialized {
+            return Ok(());
+        }
+        for director in &mut self.controlled_directors {
             let writer_mut_opt: Option<SharedWriter> = self.trace_writer.as_ref().map(Arc::clone);
-            let director_reference = Arc::new(Mutex::new(director));
-            director_reference.lock()?.init_planned(writer_mut_opt)?;
-            director_refs.push(director_reference);
+            director.init_planned(writer_mut_opt)?;
         }
         if let Some(ref val) = self.trace_writer {
-            let mut lock = val.lock().unwrap();
-            let _ = lock.upscope();
-            let _ = lock.enddefinitions();
+            let mut lock = val.lock()?;
+            lock.upscope()?;
+            lock.comment(&format!("seed {}", self.seed))?;
+            lock.enddefinitions()?;
         };
-        for director_ref in &mut director_refs {
-            director_ref.lock().unwrap().start_planned()?;
+        for director in &mut self.controlled_directors {
+            director.start_planned()?;
         }
-        if let Some(ref val) = self.trace_writer {
-            let mut lock = val.lock().unwrap();
-            let _ = lock.end();
-        };
-        for director_ref in director_refs {
-            if let Some(lock) = Arc::into_inner(director_ref) {
-                let dir = lock.into_inner()?;
-                self.controlled_directors.push(dir);
-            };
+        self.initialized = true;
+        Ok(())
+    }
+
+    fn step_directors(&mut self, only_unfinished: bool) -> Result<bool, Error> {
+        self.ensure_initialized()?;
+        let mut any_stepped = false;
+        for director in &mut self.controlled_directors {
+            if only_unfinished && director.is_finished() {
+                continue;
+            }
+            director.step_planned()?;
+            any_stepped = true;
         }
+        if any_stepped {
+            self.elapsed_steps += 1;
+            if let Some(ref writer) = self.trace_writer {
+                writer.lock()?.timestamp(self.elapsed_steps.into())?;
+            }
+        }
+        Ok(any_stepped)
+    }
+
+    /// Advances every director by exactly one time step. Neuron state is kept between calls,
+    /// so the network can be inspected or modified before the next one.
+    pub fn step(&mut self) -> Result<(), Error> {
+        self.step_directors(false)?;
+        Ok(())
+    }
+
+    pub fn run_for(&mut self, steps: u32) -> Result<(), Error> {
+        for _ in 0..steps {
+            self.step()?;
+        }
+        Ok(())
+    }
+
+    /// Steps the simulation until `predicate` returns true. The predicate is checked after
+    /// every step; returns the number of steps performed, or `Error::StepLimit` once
+    /// `max_steps` steps passed without it.
+    pub fn run_until<F>(&mut self, max_steps: u32, mut predicate: F) -> Result<u32, Error>
+    where
+        F: FnMut(&Simulation) -> bool,
+    {
+        for steps in 1..=max_steps {
+            self.step()?;
+            if predicate(self) {
+                return Ok(steps);
+            }
+        }
+        Err(Error::StepLimit(max_steps))
+    }
+
+    /// Resets every director, see `Director::reset`. Trace time keeps running, so a `Full`
+    /// reset shows up in the trace as the next trial rather than as a jump back.
+    pub fn reset(&mut self, mode: ResetMode) -> Result<(), Error> {
+        for director in &mut self.controlled_directors {
+            director.reset(mode)?;
+        }
+        Ok(())
+    }
+
+    /// Runs every director up to the `sim_time` it was created with.
+    pub fn start(&mut self) -> Result<(), Error> {
+        self.ensure_initialized()?;
+        while self.step_directors(true)? {}
         Ok(())
     }
 }
@@ -0,0 +1,20 @@
+crate::neuron_model! {
+    /// Izhikevich (2003) neuron, time steps are milliseconds and potentials millivolts. The
+    /// defaults are a regular spiking cortical cell; synaptic input moves `v` directly.
+    pub struct IzhikevichNeuron(IzhikevichParams) {
+        kind: "izhikevich";
+        parameters { a = 0.02, b = 0.2, c = -65., d = 8., current = 0., v_peak = 30. }
+        state { v = c, u = b * c }
+        input: v;
+        substeps: 2;
+        equations {
+            d(v)/dt = 0.04 * v * v + 5. * v + 140. - u + current;
+            d(u)/dt = a * (b * v - u);
+        }
+        threshold: v >= v_peak;
+        reset {
+            v = c;
+            u = u + d;
+        }
+    }
+}
@@ -1,8 +1,22 @@
 use super::*;
-use crate::ControllingUnit;
-use std::{sync::{Arc, Mutex}};
-use crate::Error;
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LifParams {
+    pub beta: f32,
+    pub threshold: f32,
+}
+
+impl LifParams {
+    pub fn new(beta: f32) -> Self {
+        Self { beta, threshold: 1. }
+    }
+
+    pub fn with_threshold(self, threshold: f32) -> Self {
+        Self { threshold, ..self }
+    }
+}
+
+/// Leaky integrate-and-fire neuron, integrated as described on [`Leaky`].
 pub struct LifNeuron {
     threshold: f32,
     current_potential: f32,
@@ -15,18 +29,20 @@ pub struct LifNeuron {
 
 impl LifNeuron {
     pub fn new(beta: f32) -> Self {
+        Self::with_params(LifParams::new(beta))
+    }
+    pub fn with_params(params: LifParams) -> Self {
         Self {
-            beta,
+            beta: params.beta,
             spikes_queue: Vec::new(),
             id: 0,
             planned_time_steps: Vec::new(),
             current_potential: 0.,
             last_leak_time: 0,
-            threshold: 1.,
+            threshold: params.threshold,
         }
     }
     pub fn add_events_entry(&mut self, step: u32) {
-        println!("\tadding events entry for time step {step}");
         self.spikes_queue.push(step);
         self.spikes_queue.sort();
     }
@@ -57,10 +73,6 @@ impl Clone for LifNeuron {
 
 impl Init for LifNeuron {
     fn init(&mut self) {
-        println!(
-            "\t{}\tCalled init!. My leak is {}",
-            self.current_potential, self.beta
-        );
         for time_step in self.planned_time_steps.clone() {
             self.emmit_signal(time_step);
         }
@@ -103,7 +115,6 @@ impl HasId for LifNeuron {
 
 impl Fire for LifNeuron {
     fn emmit_signal(&mut self, time_step: u32) {
-        println!("\t{}\tCalled emmit registrator", self.current_potential);
         self.add_events_entry(time_step);
     }
 
@@ -121,39 +132,52 @@ impl SignalReceiver for LifNeuron {
     }
 
     fn recieve_signal(&mut self, time_step: u32, signal: f32) {
-        // self.perform_leak(time_step);
-        println!(
-            "\t{}\trecieved signal of strength: {signal}!",
-            self.current_potential
-        );
         self.current_potential += signal;
         self.check_if_should_fire(time_step);
     }
 }
 
-impl Neuron for LifNeuron {}
+impl Describe for LifNeuron {
+    fn kind(&self) -> &'static str {
+        "lif"
+    }
 
-impl CommonlyCreateable for LifNeuron {
-    fn create_new(beta: f32) -> Self {
-        Self::new(beta)
+    fn parameters(&self) -> Vec<(&'static str, f32)> {
+        vec![("beta", self.beta), ("threshold", self.threshold)]
     }
-    fn batch_create_new(batch_size: usize, beta: f32) -> Vec<Self> {
-        vec![Self::new(beta); batch_size]
+}
+
+impl Adjustable for LifNeuron {
+    fn set_potential(&mut self, potential: f32) {
+        self.current_potential = potential;
+    }
+
+    fn threshold(&self) -> f32 {
+        self.threshold
+    }
+
+    fn set_threshold(&mut self, threshold: f32) {
+        self.threshold = threshold;
     }
 }
 
-impl TimeDependent for LifNeuron {
-    fn register(self, director: &mut Director) -> Result<NeuronUniqueId, Error> {
-        let passed_neuron_trait: Arc<Mutex<dyn Neuron>> = Arc::new(Mutex::new(self));
-        director.add_to_registry(passed_neuron_trait) // todo: add meaningfull error handling
-    }
-    fn register_batch(neurons_batch: Vec<Self>, director: &mut Director) -> Vec<NeuronUniqueId>
-    where
-        Self: std::marker::Sized,
-    {
-        neurons_batch
-            .into_iter()
-            .map(|neuron| neuron.register(director).unwrap())
-            .collect()
+impl Resettable for LifNeuron {
+    fn reset(&mut self, mode: ResetMode, time_step: u32) {
+        self.current_potential = 0.;
+        if mode == ResetMode::Potentials {
+            return;
+        }
+        self.spikes_queue.clear();
+        self.last_leak_time = time_step;
+    }
+}
+
+impl Neuron for LifNeuron {}
+
+impl CommonlyCreateable for LifNeuron {
+    type Params = LifParams;
+
+    fn create_new(params: LifParams) -> Self {
+        Self::with_params(params)
     }
 }
@@ -1,23 +1,49 @@
-use super::{Error, Director, NeuronUniqueId};
+use std::sync::{Arc, Mutex};
 
+use super::{ControllingUnit, Error, Director, NeuronUniqueId};
+
+pub mod model;
+pub mod izhikevich;
 pub mod lif_neuron;
 
 pub trait TimeDependent {
     fn register(self, director: &mut Director) -> Result<NeuronUniqueId, Error>;
-    fn register_batch(neurons_batch: Vec<Self>, director: &mut Director) -> Vec<NeuronUniqueId>
+    fn register_batch(neurons_batch: Vec<Self>, director: &mut Director) -> Result<Vec<NeuronUniqueId>, Error>
     where
         Self: std::marker::Sized;
 }
 
-#[allow(dead_code)] // create_new not used, but should be tested //todo
+/// Every neuron type can be handed to a director, there is nothing model specific in it.
+impl<T: Neuron + 'static> TimeDependent for T {
+    fn register(self, director: &mut Director) -> Result<NeuronUniqueId, Error> {
+        let passed_neuron_trait: Arc<Mutex<dyn Neuron>> = Arc::new(Mutex::new(self));
+        director.add_to_registry(passed_neuron_trait)
+    }
+    fn register_batch(neurons_batch: Vec<Self>, director: &mut Director) -> Result<Vec<NeuronUniqueId>, Error> {
+        neurons_batch
+            .into_iter()
+            .map(|neuron| neuron.register(director))
+            .collect()
+    }
+}
+
 pub trait CommonlyCreateable {
-    fn create_new(beta: f32) -> Self;
-    fn batch_create_new(batch_size: usize, beta: f32) -> Vec<Self>
+    /// Model parameters every neuron of a batch is created with.
+    type Params: Clone;
+
+    fn create_new(params: Self::Params) -> Self;
+    fn batch_create_new(batch_size: usize, params: Self::Params) -> Vec<Self>
     where
-        Self: std::marker::Sized;
+        Self: std::marker::Sized,
+    {
+        (0..batch_size).map(|_| Self::create_new(params.clone())).collect()
+    }
 }
 
 pub trait SignalReceiver{
+    /// Adds `signal` to the potential and fires if that reaches the threshold. The director
+    /// sums all input of a delta cycle first and always leaks the neuron to `time_step`
+    /// before, so a receiver must not leak again.
     fn recieve_signal(&mut self, time_step: u32, signal: f32); //neuron
     fn get_signal(&self) -> f32; // Neuron
 }
@@ -39,7 +65,18 @@ pub trait Fire: HasId {
     fn check_if_should_fire(&mut self, time_step: u32);
 }
 
+/// Integration contract of a step `t`, shared by every backend:
+///
+/// 1. leak: the potential decays over the steps passed since the last leak, for LIF
+///    `v(t) = beta^(t - t_last) * v(t_last)`;
+/// 2. input: stimuli, injected currents and synaptic input of spikes fired at `t` are added,
+///    undecayed;
+/// 3. threshold: a neuron at or above threshold fires at `t` and is reset to 0.
+///
+/// Below threshold this gives `v(t) = sum over s <= t of beta^(t - s) * I(s)`, counted from
+/// the last reset.
 pub trait Leaky {
+    /// Decays the potential up to `time_step`; calling it twice for the same step is a no-op.
     fn perform_leak(&mut self, time_step: u32); // Neuron
 }
 
@@ -49,4 +86,37 @@ pub trait PlansEvents {
     fn pop_earliest_event(&mut self); 
 }
 
-pub trait Neuron: Send + Sync + SignalReceiver + Init + HasId + Fire + Leaky + PlansEvents {}
\ No newline at end of file
+pub trait Describe {
+    fn kind(&self) -> &'static str;
+    fn parameters(&self) -> Vec<(&'static str, f32)>;
+}
+
+/// Direct access to the state a stimulus or a learning rule may change between steps.
+pub trait Adjustable {
+    fn set_potential(&mut self, potential: f32);
+    fn threshold(&self) -> f32;
+    fn set_threshold(&mut self, threshold: f32);
+}
+
+/// What `Director::reset` clears. Both backends follow the same rule: anything planned for
+/// later, i.e. scheduled spikes and stimuli (pulses, clamps and callbacks), is either kept or
+/// dropped as a whole. Topology, weights and injected current sources always stay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResetMode {
+    /// Only membrane potentials go back to rest; planned spikes and stimuli still arrive.
+    Potentials,
+    /// Potentials, planned spikes and stimuli, noise processes and plasticity traces are
+    /// cleared; time and recordings keep running.
+    State,
+    /// Same as `State`, and time is rewound to 0 with recordings cleared. Init impulses are
+    /// planned again, once: before the simulation started `Init::init` has yet to run anyway.
+    Full,
+}
+
+pub trait Resettable {
+    /// `time_step` is the current step of the director after the reset. Init impulses are not
+    /// planned here, the director calls `Init::init` again after a `Full` reset.
+    fn reset(&mut self, mode: ResetMode, time_step: u32);
+}
+
+pub trait Neuron: Send + Sync + SignalReceiver + Init + HasId + Fire + Leaky + PlansEvents + Describe + Adjustable + Resettable {}
\ No newline at end of file
@@ -0,0 +1,295 @@
+use super::ResetMode;
+use super::super::NeuronUniqueId;
+
+/// Bookkeeping every neuron needs regardless of its model: id, planned spikes and the step
+/// the state was last integrated to. Used by the code `neuron_model!` generates.
+#[doc(hidden)]
+#[derive(Debug, Clone, Default)]
+pub struct ModelCore {
+    id: NeuronUniqueId,
+    last_leak_time: u32,
+    spikes_queue: Vec<u32>,
+    planned_time_steps: Vec<u32>,
+}
+
+impl ModelCore {
+    pub fn id(&self) -> NeuronUniqueId {
+        self.id
+    }
+
+    pub fn set_id(&mut self, id: NeuronUniqueId) {
+        self.id = id;
+    }
+
+    pub fn schedule(&mut self, time_step: u32) {
+        let position = self.spikes_queue.partition_point(|planned| *planned <= time_step);
+        self.spikes_queue.insert(position, time_step);
+    }
+
+    pub fn earliest(&self) -> Option<&u32> {
+        self.spikes_queue.first()
+    }
+
+    pub fn pop_earliest(&mut self) {
+        self.spikes_queue.remove(0);
+    }
+
+    pub fn plan(&mut self, time_steps: Vec<u32>) {
+        self.planned_time_steps = time_steps;
+    }
+
+    pub fn planned(&self) -> &[u32] {
+        &self.planned_time_steps
+    }
+
+    /// Steps to integrate to reach `time_step`; the state counts as integrated afterwards.
+    pub fn elapsed(&mut self, time_step: u32) -> u32 {
+        let steps = time_step.abs_diff(self.last_leak_time);
+        self.last_leak_time = time_step;
+        steps
+    }
+
+    /// Pending spikes and integration time, the model state is reset by the model itself.
+    pub fn reset(&mut self, mode: ResetMode, time_step: u32) {
+        if mode == ResetMode::Potentials {
+            return;
+        }
+        self.spikes_queue.clear();
+        self.last_leak_time = time_step;
+    }
+}
+
+/// Declares a neuron model by its equations and generates the struct, its parameter struct
+/// and every trait `Director::add_population` needs.
+///
+/// ```
+/// rust_nn_framewrk::neuron_model! {
+///     /// LIF neuron with a current based synapse.
+///     pub struct CubaLif(CubaLifParams) {
+///         kind: "cuba_lif";
+///         parameters { tau = 10., tau_syn = 5., vt = 1., vr = 0. }
+///         state { v = vr, i = 0. }
+///         input: i;
+///         equations {
+///             d(v)/dt = (-v + i) / tau;
+///             d(i)/dt = -i / tau_syn;
+///         }
+///         threshold: v >= vt;
+///         reset { v = vr; }
+///     }
+/// }
+///
+/// let neuron = CubaLif::with_params(CubaLifParams { tau: 20., ..Default::default() });
+/// ```
+///
+/// - `parameters` and `state` are `f32`; state initializers may use parameters and earlier
+///   state variables, and are applied again on every director reset.
+/// - `input` is the state variable synaptic input, stimuli and currents are added to.
+/// - `equations` are integrated with forward Euler, one step per time step; an optional
+///   `substeps: n;` after `input` splits each time step into `n` Euler steps. Variables
+///   without an equation only change on input and reset.
+/// - `threshold` compares a state variable, which becomes the traced potential, with a
+///   parameter, which `Adjustable` exposes as the threshold.
+/// - `reset` statements run in order when the neuron fires.
+///
+/// The model follows the contract described on [`Leaky`](crate::neural_sim::neuron::Leaky):
+/// the equations are integrated up to the step before input of that step is added, and the
+/// threshold is checked after both. State variables must not be named `params` or `core`.
+#[macro_export]
+macro_rules! neuron_model {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident($params:ident) {
+            kind: $kind:literal;
+            parameters { $($param:ident = $param_default:expr),* $(,)? }
+            state { $($state:ident = $state_init:expr),* $(,)? }
+            input: $input:ident;
+            $(substeps: $substeps:expr;)?
+            equations { $(d($var:ident)/dt = $rhs:expr;)* }
+            threshold: $potential:ident $op:tt $threshold:ident;
+            reset { $($target:ident = $value:expr;)* }
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone)]
+        $vis struct $name {
+            params: $params,
+            core: $crate::neural_sim::neuron::model::ModelCore,
+            $($state: f32,)*
+        }
+
+        #[doc = concat!("Parameters of [`", stringify!($name), "`].")]
+        #[derive(Debug, Clone, Copy, PartialEq)]
+        $vis struct $params {
+            $(pub $param: f32,)*
+        }
+
+        impl Default for $params {
+            fn default() -> Self {
+                Self { $($param: $param_default,)* }
+            }
+        }
+
+        impl $name {
+            const SUBSTEPS: u32 = {
+                let substeps = [1 $(, $substeps)?];
+                substeps[substeps.len() - 1]
+            };
+
+            pub fn with_params(params: $params) -> Self {
+                let mut neuron = Self {
+                    params,
+                    core: Default::default(),
+                    $($state: 0.,)*
+                };
+                neuron.restore_initial_state();
+                neuron
+            }
+
+            pub fn params(&self) -> &$params {
+                &self.params
+            }
+
+            pub fn plan_init_impulses(&mut self, time_steps: Vec<u32>) {
+                self.core.plan(time_steps);
+            }
+
+            fn restore_initial_state(&mut self) {
+                #[allow(unused_variables)]
+                let $params { $($param),* } = self.params;
+                $(
+                    let $state: f32 = $state_init;
+                    self.$state = $state;
+                )*
+            }
+
+            fn integrate(&mut self, steps: u32) {
+                let h = 1. / Self::SUBSTEPS as f32;
+                #[allow(unused_variables)]
+                let $params { $($param),* } = self.params;
+                for _ in 0..steps * Self::SUBSTEPS {
+                    #[allow(unused_variables)]
+                    let ($($state,)*) = ($(self.$state,)*);
+                    ($(self.$var,)*) = ($($var + h * ($rhs),)*);
+                }
+            }
+
+            fn apply_reset(&mut self) {
+                #[allow(unused_variables)]
+                let $params { $($param),* } = self.params;
+                #[allow(unused_mut, unused_assignments)]
+                let ($(mut $state,)*) = ($(self.$state,)*);
+                $($target = $value;)*
+                ($(self.$state,)*) = ($($state,)*);
+            }
+        }
+
+        impl $crate::neural_sim::neuron::SignalReceiver for $name {
+            fn recieve_signal(&mut self, time_step: u32, signal: f32) {
+                self.$input += signal;
+                $crate::neural_sim::neuron::Fire::check_if_should_fire(self, time_step);
+            }
+
+            fn get_signal(&self) -> f32 {
+                self.$potential
+            }
+        }
+
+        impl $crate::neural_sim::neuron::Init for $name {
+            fn init(&mut self) {
+                for time_step in self.core.planned().to_vec() {
+                    $crate::neural_sim::neuron::Fire::emmit_signal(self, time_step);
+                }
+            }
+        }
+
+        impl $crate::neural_sim::neuron::HasId for $name {
+            fn set_id(&mut self, id: $crate::neural_sim::NeuronUniqueId) {
+                self.core.set_id(id);
+            }
+
+            fn get_id(&self) -> Option<u32> {
+                Some(self.core.id())
+            }
+        }
+
+        impl $crate::neural_sim::neuron::Fire for $name {
+            fn emmit_signal(&mut self, time_step: u32) {
+                self.core.schedule(time_step);
+            }
+
+            fn check_if_should_fire(&mut self, time_step: u32) {
+                if self.$potential $op self.params.$threshold {
+                    self.emmit_signal(time_step);
+                    self.apply_reset();
+                }
+            }
+        }
+
+        impl $crate::neural_sim::neuron::Leaky for $name {
+            /// Integrates the equations up to `time_step`; the dynamics alone may cross the threshold.
+            fn perform_leak(&mut self, time_step: u32) {
+                let steps = self.core.elapsed(time_step);
+                if steps != 0 {
+                    self.integrate(steps);
+                    $crate::neural_sim::neuron::Fire::check_if_should_fire(self, time_step);
+                }
+            }
+        }
+
+        impl $crate::neural_sim::neuron::PlansEvents for $name {
+            fn get_earliest_event(&self) -> Option<&u32> {
+                self.core.earliest()
+            }
+
+            fn get_earliest_event_available(&self) -> Option<bool> {
+                Some(self.core.earliest().is_some())
+            }
+
+            fn pop_earliest_event(&mut self) {
+                self.core.pop_earliest();
+            }
+        }
+
+        impl $crate::neural_sim::neuron::Describe for $name {
+            fn kind(&self) -> &'static str {
+                $kind
+            }
+
+            fn parameters(&self) -> Vec<(&'static str, f32)> {
+                vec![$((stringify!($param), self.params.$param)),*]
+            }
+        }
+
+        impl $crate::neural_sim::neuron::Adjustable for $name {
+            fn set_potential(&mut self, potential: f32) {
+                self.$potential = potential;
+            }
+
+            fn threshold(&self) -> f32 {
+                self.params.$threshold
+            }
+
+            fn set_threshold(&mut self, threshold: f32) {
+                self.params.$threshold = threshold;
+            }
+        }
+
+        impl $crate::neural_sim::neuron::Resettable for $name {
+            fn reset(&mut self, mode: $crate::neural_sim::neuron::ResetMode, time_step: u32) {
+                self.restore_initial_state();
+                self.core.reset(mode, time_step);
+            }
+        }
+
+        impl $crate::neural_sim::neuron::Neuron for $name {}
+
+        impl $crate::neural_sim::neuron::CommonlyCreateable for $name {
+            type Params = $params;
+
+            fn create_new(params: $params) -> Self {
+                Self::with_params(params)
+            }
+        }
+    };
+}
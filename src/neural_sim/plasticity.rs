@@ -0,0 +1,286 @@
+use std::collections::HashMap;
+
+use super::error::Error;
+use super::stimulus::NeuronControl;
+use super::{Director, NeuronRegistrator, NeuronUniqueId, Simulation};
+
+/// Weights a plasticity rule may read and change at the end of a step.
+pub struct Synapses<'a> {
+    planner: &'a mut NeuronRegistrator,
+}
+
+impl Synapses<'_> {
+    pub fn weight(&self, source: NeuronUniqueId, destination: NeuronUniqueId) -> Option<f32> {
+        self.planner.connection_map.get(&source)?.iter().find(|pair| pair.id == destination).map(|pair| pair.weight)
+    }
+
+    /// Sets the weight of every link from `source` to `destination`.
+    pub fn set_weight(&mut self, source: NeuronUniqueId, destination: NeuronUniqueId, weight: f32) -> Result<(), Error> {
+        self.planner.set_weight(source, destination, weight)
+    }
+
+    /// Changes every link from `source` to `destination` on its own, parallel links included.
+    pub fn update_weights<F>(&mut self, source: NeuronUniqueId, destination: NeuronUniqueId, update: F) -> Result<(), Error>
+    where
+        F: FnMut(f32) -> f32,
+    {
+        self.planner.update_weights(source, destination, update)
+    }
+}
+
+/// Learning rule run by a director once every step is over.
+pub trait Plasticity {
+    /// `fired` holds every spike of `time_step`, in the order they were emitted. Changes to
+    /// `neurons` take effect from the next step on.
+    fn step(
+        &mut self,
+        time_step: u32,
+        fired: &[NeuronUniqueId],
+        synapses: &mut Synapses,
+        neurons: &mut NeuronControl,
+    ) -> Result<(), Error>;
+
+    /// Neuromodulator released on `channel`; takes effect on the next step.
+    fn modulate(&mut self, _channel: &str, _amount: f32) {}
+
+    /// Forgets traces between trials, learned weights are kept.
+    fn reset(&mut self, _neurons: &mut NeuronControl) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RewardStdpParams {
+    /// Eligibility added when a postsynaptic spike follows a presynaptic one.
+    pub a_plus: f32,
+    /// Eligibility removed when a presynaptic spike follows a postsynaptic one.
+    pub a_minus: f32,
+    /// Time constants in steps of the pre- and postsynaptic spike traces.
+    pub tau_plus: f32,
+    pub tau_minus: f32,
+    pub tau_eligibility: f32,
+    pub tau_modulator: f32,
+    pub learning_rate: f32,
+    /// Bounds of excitatory weights. Synapses that are inhibitory (negative) when the rule is
+    /// created are kept within `[-w_max, -w_min]`, so learning never flips their sign.
+    pub w_min: f32,
+    pub w_max: f32,
+}
+
+impl Default for RewardStdpParams {
+    fn default() -> Self {
+        Self {
+            a_plus: 1.,
+            a_minus: 1.,
+            tau_plus: 20.,
+            tau_minus: 20.,
+            tau_eligibility: 100.,
+            tau_modulator: 10.,
+            learning_rate: 0.01,
+            w_min: 0.,
+            w_max: 1.,
+        }
+    }
+}
+
+/// Three-factor STDP: spike pairings only mark synapses as eligible, and weights change by
+/// `learning_rate * modulator * eligibility` every step while a neuromodulator is present.
+///
+/// Spikes of the same step count as causal, since a presynaptic spike is delivered within
+/// the step it was emitted in.
+pub struct RewardStdp {
+    params: RewardStdpParams,
+    channel: String,
+    synapses: Vec<(NeuronUniqueId, NeuronUniqueId)>,
+    /// Synapses whose weight was negative when the rule was created.
+    inhibitory: Vec<bool>,
+    eligibility: Vec<f32>,
+    outgoing: HashMap<NeuronUniqueId, Vec<usize>>,
+    incoming: HashMap<NeuronUniqueId, Vec<usize>>,
+    pre_traces: HashMap<NeuronUniqueId, f32>,
+    post_traces: HashMap<NeuronUniqueId, f32>,
+    modulator: f32,
+    last_step: Option<u32>,
+}
+
+impl RewardStdp {
+    pub const DEFAULT_CHANNEL: &'static str = "dopamine";
+
+    /// Governs every existing link from `sources` to `destinations`.
+    pub fn new(
+        director: &Director,
+        sources: &[NeuronUniqueId],
+        destinations: &[NeuronUniqueId],
+        params: RewardStdpParams,
+    ) -> Result<Self, Error> {
+        let mut rule = Self {
+            params,
+            channel: Self::DEFAULT_CHANNEL.to_string(),
+            synapses: Vec::new(),
+            inhibitory: Vec::new(),
+            eligibility: Vec::new(),
+            outgoing: HashMap::new(),
+            incoming: HashMap::new(),
+            pre_traces: HashMap::new(),
+            post_traces: HashMap::new(),
+            modulator: 0.,
+            last_step: None,
+        };
+        for source in sources {
+            for link in director.outgoing_links(*source)? {
+                if !destinations.contains(&link.destination) || rule.synapses.contains(&(link.source, link.destination)) {
+                    continue;
+                }
+                let index = rule.synapses.len();
+                rule.synapses.push((link.source, link.destination));
+                rule.inhibitory.push(link.weight < 0.);
+                rule.outgoing.entry(link.source).or_default().push(index);
+                rule.incoming.entry(link.destination).or_default().push(index);
+            }
+        }
+        rule.eligibility = vec![0.; rule.synapses.len()];
+        Ok(rule)
+    }
+
+    /// Listens to `channel` instead of the default one, e.g. for a per-population reward.
+    pub fn with_channel(mut self, channel: &str) -> Self {
+        self.channel = channel.to_string();
+        self
+    }
+
+    pub fn synapses(&self) -> &[(NeuronUniqueId, NeuronUniqueId)] {
+        &self.synapses
+    }
+
+    pub fn eligibility(&self, source: NeuronUniqueId, destination: NeuronUniqueId) -> Option<f32> {
+        let index = self.synapses.iter().position(|synapse| *synapse == (source, destination))?;
+        Some(self.eligibility[index])
+    }
+
+    pub fn modulator(&self) -> f32 {
+        self.modulator
+    }
+}
+
+impl Plasticity for RewardStdp {
+    fn step(
+        &mut self,
+        time_step: u32,
+        fired: &[NeuronUniqueId],
+        synapses: &mut Synapses,
+        _neurons: &mut NeuronControl,
+    ) -> Result<(), Error> {
+        let elapsed = self.last_step.map_or(1., |last| time_step.saturating_sub(last) as f32);
+        self.last_step = Some(time_step);
+        let decay = |tau: f32| (-elapsed / tau).exp();
+        let (pre_decay, post_decay) = (decay(self.params.tau_plus), decay(self.params.tau_minus));
+        self.pre_traces.values_mut().for_each(|trace| *trace *= pre_decay);
+        self.post_traces.values_mut().for_each(|trace| *trace *= post_decay);
+        let eligibility_decay = decay(self.params.tau_eligibility);
+        self.eligibility.iter_mut().for_each(|eligibility| *eligibility *= eligibility_decay);
+
+        /* presynaptic spikes pair with earlier postsynaptic ones before their own trace grows */
+        for id in fired {
+            if let Some(indices) = self.outgoing.get(id) {
+                for index in indices {
+                    let post_trace = self.post_traces.get(&self.synapses[*index].1).copied().unwrap_or(0.);
+                    self.eligibility[*index] -= self.params.a_minus * post_trace;
+                }
+                *self.pre_traces.entry(*id).or_default() += 1.;
+            }
+        }
+        for id in fired {
+            if let Some(indices) = self.incoming.get(id) {
+                for index in indices {
+                    let pre_trace = self.pre_traces.get(&self.synapses[*index].0).copied().unwrap_or(0.);
+                    self.eligibility[*index] += self.params.a_plus * pre_trace;
+                }
+                *self.post_traces.entry(*id).or_default() += 1.;
+            }
+        }
+
+        if self.modulator != 0. {
+            for (index, (source, destination)) in self.synapses.iter().enumerate() {
+                let change = self.params.learning_rate * self.modulator * self.eligibility[index];
+                if change == 0. {
+                    continue;
+                }
+                let weight = synapses.weight(*source, *destination).ok_or(Error::UnknownLink { source: *source, destination: *destination })?;
+                let (low, high) = if self.inhibitory[index] {
+                    (-self.params.w_max, -self.params.w_min)
+                } else {
+                    (self.params.w_min, self.params.w_max)
+                };
+                synapses.set_weight(*source, *destination, (weight + change).clamp(low, high))?;
+            }
+            self.modulator *= decay(self.params.tau_modulator);
+        }
+        Ok(())
+    }
+
+    fn modulate(&mut self, channel: &str, amount: f32) {
+        if channel == self.channel {
+            self.modulator += amount;
+        }
+    }
+
+    fn reset(&mut self, _neurons: &mut NeuronControl) -> Result<(), Error> {
+        self.eligibility.iter_mut().for_each(|eligibility| *eligibility = 0.);
+        self.pre_traces.clear();
+        self.post_traces.clear();
+        self.modulator = 0.;
+        self.last_step = None;
+        Ok(())
+    }
+}
+
+impl Director {
+    pub fn add_plasticity(&mut self, rule: impl Plasticity + 'static) {
+        self.plasticity.push(Box::new(rule));
+    }
+
+    pub fn release_neuromodulator(&mut self, channel: &str, amount: f32) {
+        for rule in &mut self.plasticity {
+            rule.modulate(channel, amount);
+        }
+    }
+
+    pub(super) fn apply_plasticity(&mut self, time_step: u32) -> Result<(), Error> {
+        let first_id = self.planner.first_id;
+        let mut synapses = Synapses { planner: &mut self.planner };
+        let mut neurons = NeuronControl {
+            slots: &self.slots,
+            first_id,
+            vectorized: &mut self.vectorized,
+            input: &mut self.input_buffer,
+            time_step,
+        };
+        for rule in &mut self.plasticity {
+            rule.step(time_step, &self.fired_last_step, &mut synapses, &mut neurons)?;
+        }
+        Ok(())
+    }
+
+    pub(super) fn reset_plasticity(&mut self) -> Result<(), Error> {
+        let mut neurons = NeuronControl {
+            slots: &self.slots,
+            first_id: self.planner.first_id,
+            vectorized: &mut self.vectorized,
+            input: &mut self.input_buffer,
+            time_step: self.cur_time,
+        };
+        for rule in &mut self.plasticity {
+            rule.reset(&mut neurons)?;
+        }
+        Ok(())
+    }
+}
+
+impl Simulation {
+    /// Releases a neuromodulator to the plasticity rules of every director.
+    pub fn release_neuromodulator(&mut self, channel: &str, amount: f32) {
+        for director in &mut self.controlled_directors {
+            director.release_neuromodulator(channel, amount);
+        }
+    }
+}
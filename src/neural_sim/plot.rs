@@ -0,0 +1,419 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::ops::Range;
+
+use super::analysis::SpikeTrains;
+use super::population::Population;
+use super::{Director, NeuronUniqueId};
+
+const PALETTE: [&str; 8] = ["#1f77b4", "#d62728", "#2ca02c", "#ff7f0e", "#9467bd", "#8c564b", "#e377c2", "#17becf"];
+const ANSI_PALETTE: [u8; 6] = [34, 31, 32, 33, 35, 36];
+const UNASSIGNED_COLOR: &str = "#7f7f7f";
+const LEFT: f32 = 90.;
+const RIGHT: f32 = 20.;
+const TOP: f32 = 20.;
+const BOTTOM: f32 = 40.;
+
+/// Renders spikes and potentials recorded by a director as SVG or as braille text for terminals.
+///
+/// Spikes come from `Director::record_spikes` and potentials from `Director::record_potentials`;
+/// the plotted window defaults to every step run so far.
+///
+/// Potentials are recorded at the end of a step, after a spiking neuron was reset, so a trace
+/// never reaches the threshold line drawn with it: a spike shows as a drop to 0 instead.
+pub struct Plot<'a> {
+    spikes: &'a [(NeuronUniqueId, u32)],
+    potentials: &'a BTreeMap<NeuronUniqueId, Vec<(u32, f32)>>,
+    populations: &'a [Population],
+    population_of: HashMap<NeuronUniqueId, usize>,
+    thresholds: HashMap<NeuronUniqueId, f32>,
+    /// Row 0 of a raster, ids of one director are contiguous from it.
+    first_id: NeuronUniqueId,
+    neuron_count: usize,
+    window: Range<u32>,
+    width: f32,
+    height: f32,
+    color: bool,
+}
+
+impl<'a> Plot<'a> {
+    pub fn new(director: &'a Director) -> Self {
+        let populations = director.populations();
+        let population_of = populations
+            .iter()
+            .enumerate()
+            .flat_map(|(index, population)| population.iter().map(move |id| (*id, index)))
+            .collect();
+        let thresholds = director
+            .recorded_potentials()
+            .keys()
+            .filter_map(|id| {
+                let info = director.neuron_info(*id).ok()?;
+                let threshold = info.parameters.iter().find(|(name, _)| *name == "threshold")?.1;
+                Some((*id, threshold))
+            })
+            .collect();
+        Self {
+            spikes: director.recorded_spikes(),
+            potentials: director.recorded_potentials(),
+            populations,
+            population_of,
+            thresholds,
+            first_id: director.neuron_ids().first().copied().unwrap_or(0),
+            neuron_count: director.neuron_ids().len(),
+            window: 0..director.cur_time(),
+            width: 800.,
+            height: 400.,
+            color: true,
+        }
+    }
+
+    pub fn with_window(mut self, window: Range<u32>) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Size of SVG output in pixels.
+    pub fn with_size(mut self, width: f32, height: f32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Toggles ANSI colors in terminal output.
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    fn duration(&self) -> u32 {
+        self.window.end.saturating_sub(self.window.start).max(1)
+    }
+
+    fn row(&self, id: NeuronUniqueId) -> usize {
+        id.saturating_sub(self.first_id) as usize
+    }
+
+    fn visible_spikes(&self) -> impl Iterator<Item = &(NeuronUniqueId, u32)> + '_ {
+        self.spikes.iter().filter(|(_, time_step)| self.window.contains(time_step))
+    }
+
+    fn svg_color(&self, id: NeuronUniqueId) -> &'static str {
+        self.population_of.get(&id).map_or(UNASSIGNED_COLOR, |index| PALETTE[index % PALETTE.len()])
+    }
+
+    fn plot_area(&self) -> (f32, f32) {
+        ((self.width - LEFT - RIGHT).max(1.), (self.height - TOP - BOTTOM).max(1.))
+    }
+
+    fn x_of(&self, time_step: f32) -> f32 {
+        LEFT + (time_step - self.window.start as f32) / self.duration() as f32 * self.plot_area().0
+    }
+
+    fn svg_begin(&self) -> String {
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}" font-family="sans-serif" font-size="11">"#,
+            self.width, self.height
+        );
+        let _ = writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#);
+        svg
+    }
+
+    /// Frame of the plot area with time ticks below it.
+    fn svg_time_axis(&self, svg: &mut String, top: f32, height: f32) {
+        let (width, _) = self.plot_area();
+        let _ = writeln!(svg, r#"<rect x="{LEFT}" y="{top}" width="{width}" height="{height}" fill="none" stroke="black"/>"#);
+        for tick in 0..=4 {
+            let time_step = self.window.start as f32 + self.duration() as f32 * tick as f32 / 4.;
+            let x = self.x_of(time_step);
+            let y = top + height;
+            let _ = writeln!(svg, r#"<line x1="{x}" y1="{y}" x2="{x}" y2="{}" stroke="black"/>"#, y + 4.);
+            let _ = writeln!(svg, r#"<text x="{x}" y="{}" text-anchor="middle">{}</text>"#, y + 16., time_step.round());
+        }
+        let _ = writeln!(
+            svg,
+            r#"<text x="{}" y="{}" text-anchor="middle">time step</text>"#,
+            LEFT + width / 2.,
+            self.height - 6.
+        );
+    }
+
+    /// One row per neuron, one mark per spike, colored by population.
+    pub fn raster_svg(&self) -> String {
+        let (width, height) = self.plot_area();
+        let rows = self.neuron_count.max(1) as f32;
+        let row_height = height / rows;
+        let mark_width = (width / self.duration() as f32).max(1.);
+        let mut svg = self.svg_begin();
+
+        for (index, population) in self.populations.iter().enumerate() {
+            let (Some(first), Some(last)) = (population.iter().min(), population.iter().max()) else {
+                continue;
+            };
+            let (first, last) = (self.row(*first), self.row(*last));
+            let y = TOP + (first as f32 + (last - first + 1) as f32 / 2.) * row_height;
+            let _ = writeln!(
+                svg,
+                r#"<text x="{}" y="{y}" text-anchor="end" dominant-baseline="middle" fill="{}">{}</text>"#,
+                LEFT - 6.,
+                PALETTE[index % PALETTE.len()],
+                escape_xml(population.name())
+            );
+            if first > 0 {
+                let y = TOP + first as f32 * row_height;
+                let _ = writeln!(svg, r##"<line x1="{LEFT}" y1="{y}" x2="{}" y2="{y}" stroke="#cccccc"/>"##, LEFT + width);
+            }
+        }
+        for (id, time_step) in self.visible_spikes() {
+            let _ = writeln!(
+                svg,
+                r#"<rect x="{}" y="{}" width="{mark_width}" height="{}" fill="{}"/>"#,
+                self.x_of(*time_step as f32),
+                TOP + self.row(*id) as f32 * row_height + row_height * 0.1,
+                (row_height * 0.8).max(0.5),
+                self.svg_color(*id)
+            );
+        }
+        self.svg_time_axis(&mut svg, TOP, height);
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Stacked panels with the rate of every population in bins of `bin_width` steps, in
+    /// spikes per step and neuron.
+    pub fn rate_histogram_svg(&self, bin_width: u32) -> String {
+        let (width, height) = self.plot_area();
+        let spikes: Vec<(NeuronUniqueId, u32)> = self.visible_spikes().copied().collect();
+        let panels: Vec<(String, &str, Vec<f32>)> = if self.populations.is_empty() {
+            let trains = SpikeTrains::new(&spikes, self.window.clone());
+            vec![("all".to_string(), UNASSIGNED_COLOR, trains.population_rate_histogram(bin_width))]
+        } else {
+            self.populations
+                .iter()
+                .enumerate()
+                .map(|(index, population)| {
+                    let own: Vec<(NeuronUniqueId, u32)> = spikes.iter().filter(|(id, _)| population.contains(*id)).copied().collect();
+                    let trains = SpikeTrains::new(&own, self.window.clone()).with_neurons(population);
+                    (population.name().to_string(), PALETTE[index % PALETTE.len()], trains.population_rate_histogram(bin_width))
+                })
+                .collect()
+        };
+        let max_rate = panels.iter().flat_map(|(_, _, rates)| rates.iter().copied()).fold(0., f32::max).max(f32::EPSILON);
+        let panel_height = height / panels.len() as f32;
+        let bin_width = bin_width.max(1);
+        let mut svg = self.svg_begin();
+
+        for (panel, (name, color, rates)) in panels.iter().enumerate() {
+            let bottom = TOP + (panel + 1) as f32 * panel_height;
+            for (bin, rate) in rates.iter().enumerate() {
+                let from = self.window.start + bin as u32 * bin_width;
+                let to = (from + bin_width).min(self.window.end);
+                let bar_height = rate / max_rate * panel_height * 0.9;
+                let _ = writeln!(
+                    svg,
+                    r#"<rect x="{}" y="{}" width="{}" height="{bar_height}" fill="{color}"/>"#,
+                    self.x_of(from as f32),
+                    bottom - bar_height,
+                    (self.x_of(to as f32) - self.x_of(from as f32)).max(0.5)
+                );
+            }
+            let _ = writeln!(
+                svg,
+                r#"<text x="{}" y="{}" text-anchor="end" dominant-baseline="middle" fill="{color}">{}</text>"#,
+                LEFT - 6.,
+                bottom - panel_height / 2.,
+                escape_xml(name)
+            );
+            if panel > 0 {
+                let y = bottom - panel_height;
+                let _ = writeln!(svg, r##"<line x1="{LEFT}" y1="{y}" x2="{}" y2="{y}" stroke="#cccccc"/>"##, LEFT + width);
+            }
+        }
+        let _ = writeln!(svg, r#"<text x="{}" y="{}" text-anchor="end">max {max_rate:.3} spikes/step</text>"#, LEFT + width, TOP - 6.);
+        self.svg_time_axis(&mut svg, TOP, height);
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    fn potential_range(&self, ids: &[NeuronUniqueId]) -> (f32, f32) {
+        let values = ids.iter().flat_map(|id| {
+            let series = self.potentials.get(id).map_or(&[][..], Vec::as_slice);
+            series
+                .iter()
+                .filter(|(time_step, _)| self.window.contains(time_step))
+                .map(|(_, potential)| *potential)
+                .chain(self.thresholds.get(id).copied())
+        });
+        let (low, high) = values.fold((f32::INFINITY, f32::NEG_INFINITY), |(low, high), value| (low.min(value), high.max(value)));
+        match (low.is_finite(), high - low > f32::EPSILON) {
+            (false, _) => (0., 1.),
+            (true, false) => (low - 0.5, high + 0.5),
+            (true, true) => (low, high),
+        }
+    }
+
+    /// Potential traces of `ids`, all recorded neurons if empty, with dashed thresholds.
+    pub fn potentials_svg(&self, ids: &[NeuronUniqueId]) -> String {
+        let ids: Vec<NeuronUniqueId> = if ids.is_empty() { self.potentials.keys().copied().collect() } else { ids.to_vec() };
+        let (width, height) = self.plot_area();
+        let (low, high) = self.potential_range(&ids);
+        let y_of = |potential: f32| TOP + (high - potential) / (high - low) * height;
+        let mut svg = self.svg_begin();
+
+        for (index, id) in ids.iter().enumerate() {
+            let color = PALETTE[index % PALETTE.len()];
+            let series = self.potentials.get(id).map_or(&[][..], Vec::as_slice);
+            let points: Vec<String> = series
+                .iter()
+                .filter(|(time_step, _)| self.window.contains(time_step))
+                .map(|(time_step, potential)| format!("{:.2},{:.2}", self.x_of(*time_step as f32), y_of(*potential)))
+                .collect();
+            let _ = writeln!(svg, r#"<polyline points="{}" fill="none" stroke="{color}"/>"#, points.join(" "));
+            if let Some(threshold) = self.thresholds.get(id) {
+                let y = y_of(*threshold);
+                let _ = writeln!(
+                    svg,
+                    r#"<line x1="{LEFT}" y1="{y}" x2="{}" y2="{y}" stroke="{color}" stroke-dasharray="4 3"/>"#,
+                    LEFT + width
+                );
+            }
+            let _ = writeln!(svg, r#"<text x="{}" y="{}" text-anchor="end" fill="{color}">neuron {id}</text>"#, LEFT + width - 4., TOP + 14. * (index + 1) as f32);
+        }
+        for (value, y) in [(high, TOP), (low, TOP + height)] {
+            let _ = writeln!(svg, r#"<text x="{}" y="{y}" text-anchor="end" dominant-baseline="middle">{value:.3}</text>"#, LEFT - 6.);
+        }
+        self.svg_time_axis(&mut svg, TOP, height);
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    fn ansi(&self, text: &str, index: Option<usize>) -> String {
+        match (self.color, index) {
+            (true, Some(index)) => format!("\x1b[{}m{text}\x1b[0m", ANSI_PALETTE[index % ANSI_PALETTE.len()]),
+            _ => text.to_string(),
+        }
+    }
+
+    /// Raster drawn with braille characters, `columns` by `rows` cells, each covering 2 by 4 dots.
+    pub fn raster_terminal(&self, columns: usize, rows: usize) -> String {
+        let mut canvas = BrailleCanvas::new(columns, rows);
+        let neurons = self.neuron_count.max(1);
+        for (id, time_step) in self.visible_spikes() {
+            let x = ((time_step - self.window.start) as usize * canvas.dot_width()) / self.duration() as usize;
+            let y = (self.row(*id) * canvas.dot_height()) / neurons;
+            canvas.set(x, y);
+        }
+        let mut text = format!(
+            "steps {}..{}, neurons {}..{}\n",
+            self.window.start,
+            self.window.end,
+            self.first_id,
+            self.first_id as usize + self.neuron_count
+        );
+        for (row, line) in canvas.lines().enumerate() {
+            /* a text row is colored and labeled after the neuron in its middle */
+            let id = self.first_id + ((row * 4 + 2) * neurons / canvas.dot_height()) as NeuronUniqueId;
+            let population = self.population_of.get(&id).copied();
+            let label = population.map_or("", |index| self.populations[index].name());
+            let _ = writeln!(text, "{} {}", self.ansi(&line, population), self.ansi(label, population));
+        }
+        text
+    }
+
+    /// Potential of one neuron drawn with braille characters; the threshold is dotted.
+    pub fn potential_terminal(&self, id: NeuronUniqueId, columns: usize, rows: usize) -> String {
+        let mut canvas = BrailleCanvas::new(columns, rows);
+        let (low, high) = self.potential_range(&[id]);
+        let bottom = canvas.dot_height() - 1;
+        let y_of = |potential: f32| ((high - potential) / (high - low) * bottom as f32).round().clamp(0., bottom as f32) as usize;
+        let series: Vec<(u32, f32)> = self
+            .potentials
+            .get(&id)
+            .map_or(&[][..], Vec::as_slice)
+            .iter()
+            .filter(|(time_step, _)| self.window.contains(time_step))
+            .copied()
+            .collect();
+
+        if let Some(threshold) = self.thresholds.get(&id) {
+            for x in (0..canvas.dot_width()).step_by(3) {
+                canvas.set(x, y_of(*threshold));
+            }
+        }
+        let mut previous: Option<usize> = None;
+        for x in 0..canvas.dot_width() {
+            let time_step = self.window.start + (x * self.duration() as usize / canvas.dot_width()) as u32;
+            let position = series.partition_point(|(recorded, _)| *recorded <= time_step);
+            let Some((_, potential)) = position.checked_sub(1).map(|position| series[position]) else {
+                continue;
+            };
+            let y = y_of(potential);
+            let (from, to) = previous.map_or((y, y), |previous| (previous.min(y), previous.max(y)));
+            for y in from..=to {
+                canvas.set(x, y);
+            }
+            previous = Some(y);
+        }
+
+        let population = self.population_of.get(&id).copied();
+        let mut text = format!("neuron {id}, steps {}..{}, potential {low:.3}..{high:.3}\n", self.window.start, self.window.end);
+        for line in canvas.lines() {
+            let _ = writeln!(text, "{}", self.ansi(&line, population));
+        }
+        text
+    }
+}
+
+/// `text` with the characters that may not appear as is in SVG text or attributes escaped.
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(character),
+        }
+    }
+    escaped
+}
+
+/// Grid of braille cells, addressed by dot.
+struct BrailleCanvas {
+    columns: usize,
+    rows: usize,
+    cells: Vec<u8>,
+}
+
+impl BrailleCanvas {
+    /// Bits of the dots of a cell, indexed by `[y][x]`.
+    const DOTS: [[u8; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+    fn new(columns: usize, rows: usize) -> Self {
+        let (columns, rows) = (columns.max(1), rows.max(1));
+        Self { columns, rows, cells: vec![0; columns * rows] }
+    }
+
+    fn dot_width(&self) -> usize {
+        self.columns * 2
+    }
+
+    fn dot_height(&self) -> usize {
+        self.rows * 4
+    }
+
+    fn set(&mut self, x: usize, y: usize) {
+        if x < self.dot_width() && y < self.dot_height() {
+            self.cells[(y / 4) * self.columns + x / 2] |= Self::DOTS[y % 4][x % 2];
+        }
+    }
+
+    fn lines(&self) -> impl Iterator<Item = String> + '_ {
+        self.cells
+            .chunks(self.columns)
+            .map(|row| row.iter().map(|cell| char::from_u32(0x2800 + *cell as u32).unwrap_or(' ')).collect())
+    }
+}
@@ -0,0 +1,234 @@
+use std::fmt;
+use std::ops::{Deref, Range};
+
+use super::error::{Error, PopulationError};
+use super::neuron::{CommonlyCreateable, Neuron, TimeDependent};
+use super::{Director, NeuronUniqueId};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shape {
+    D1(usize),
+    D2(usize, usize),
+    D3(usize, usize, usize),
+}
+
+impl Shape {
+    pub fn dimensions(&self) -> Vec<usize> {
+        match *self {
+            Self::D1(x) => vec![x],
+            Self::D2(x, y) => vec![x, y],
+            Self::D3(x, y, z) => vec![x, y, z],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.dimensions().iter().product()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Row-major position of `coords`; the last coordinate changes fastest.
+    pub fn index(&self, coords: &[usize]) -> Option<usize> {
+        let dimensions = self.dimensions();
+        if coords.len() != dimensions.len() {
+            return None;
+        }
+        let mut index = 0;
+        for (coord, size) in coords.iter().zip(&dimensions) {
+            if coord >= size {
+                return None;
+            }
+            index = index * size + coord;
+        }
+        Some(index)
+    }
+
+    fn from_dimensions(dimensions: &[usize]) -> Self {
+        match *dimensions {
+            [x] => Self::D1(x),
+            [x, y] => Self::D2(x, y),
+            [x, y, z] => Self::D3(x, y, z),
+            _ => unreachable!("shapes have one to three dimensions"),
+        }
+    }
+}
+
+impl fmt::Display for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let dimensions: Vec<String> = self.dimensions().iter().map(usize::to_string).collect();
+        write!(f, "{}", dimensions.join("x"))
+    }
+}
+
+/// Named group of neurons registered together. Dereferences to the slice of its ids,
+/// so it can be passed wherever `&[NeuronUniqueId]` is expected.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Population {
+    name: String,
+    shape: Shape,
+    kind: &'static str,
+    ids: Vec<NeuronUniqueId>,
+}
+
+impl Population {
+    pub(super) fn new(name: &str, shape: Shape, kind: &'static str, ids: Vec<NeuronUniqueId>) -> Self {
+        Self {
+            name: name.to_string(),
+            shape,
+            kind,
+            ids,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn shape(&self) -> Shape {
+        self.shape
+    }
+
+    pub fn kind(&self) -> &'static str {
+        self.kind
+    }
+
+    pub fn ids(&self) -> &[NeuronUniqueId] {
+        &self.ids
+    }
+
+    /// Range of ids covered by the population, `None` for slices that are not contiguous.
+    pub fn id_range(&self) -> Option<Range<NeuronUniqueId>> {
+        let first = *self.ids.first()?;
+        let contiguous = self.ids.iter().zip(first..).all(|(id, expected)| *id == expected);
+        contiguous.then(|| first..first + self.ids.len() as NeuronUniqueId)
+    }
+
+    pub fn contains(&self, id: NeuronUniqueId) -> bool {
+        self.ids.contains(&id)
+    }
+
+    pub fn at(&self, coords: &[usize]) -> Option<NeuronUniqueId> {
+        self.shape.index(coords).map(|index| self.ids[index])
+    }
+
+    /// Sub-population selected by one coordinate range per dimension.
+    pub fn slice(&self, ranges: &[Range<usize>]) -> Result<Population, Error> {
+        let dimensions = self.shape.dimensions();
+        if ranges.len() != dimensions.len() {
+            return Err(Error::Population(PopulationError::Dimensions {
+                expected: dimensions.len(),
+                found: ranges.len(),
+            }));
+        }
+        for (dimension, (range, size)) in ranges.iter().zip(&dimensions).enumerate() {
+            if range.start > range.end || range.end > *size {
+                return Err(Error::Population(PopulationError::OutOfRange {
+                    dimension,
+                    end: range.end,
+                    size: *size,
+                }));
+            }
+        }
+
+        let mut ids = Vec::new();
+        let mut coords: Vec<usize> = ranges.iter().map(|range| range.start).collect();
+        if ranges.iter().all(|range| !range.is_empty()) {
+            'outer: loop {
+                ids.push(self.ids[self.shape.index(&coords).unwrap()]);
+                for axis in (0..coords.len()).rev() {
+                    coords[axis] += 1;
+                    if coords[axis] < ranges[axis].end {
+                        continue 'outer;
+                    }
+                    coords[axis] = ranges[axis].start;
+                }
+                break;
+            }
+        }
+
+        let lengths: Vec<usize> = ranges.iter().map(|range| range.len()).collect();
+        let bounds: Vec<String> = ranges.iter().map(|range| format!("{}..{}", range.start, range.end)).collect();
+        Ok(Population {
+            name: format!("{}[{}]", self.name, bounds.join(",")),
+            shape: Shape::from_dimensions(&lengths),
+            kind: self.kind,
+            ids,
+        })
+    }
+
+    /// Name usable as a VCD scope identifier.
+    pub(super) fn trace_scope_name(&self) -> String {
+        self.name.replace(char::is_whitespace, "_")
+    }
+}
+
+impl Deref for Population {
+    type Target = [NeuronUniqueId];
+
+    fn deref(&self) -> &Self::Target {
+        &self.ids
+    }
+}
+
+impl fmt::Display for Population {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({} {}", self.name, self.shape, self.kind)?;
+        match self.id_range() {
+            Some(range) => write!(f, ", ids {}..{})", range.start, range.end),
+            None => write!(f, ", {} ids)", self.ids.len()),
+        }
+    }
+}
+
+impl Director {
+    /// Registers `neurons` as a named population laid out in `shape`.
+    pub fn register_population<T>(&mut self, name: &str, shape: Shape, neurons: Vec<T>) -> Result<Population, Error>
+    where
+        T: Neuron + 'static,
+    {
+        if neurons.len() != shape.len() {
+            return Err(Error::Population(PopulationError::ShapeMismatch {
+                expected: shape.len(),
+                found: neurons.len(),
+            }));
+        }
+        self.check_population_name(name)?;
+
+        let kind = neurons.first().map_or("empty", |neuron| neuron.kind());
+        let ids = T::register_batch(neurons, self)?;
+        let population = Population::new(name, shape, kind, ids);
+        self.populations.push(population.clone());
+        Ok(population)
+    }
+
+    pub(super) fn check_population_name(&self, name: &str) -> Result<(), Error> {
+        match self.population(name) {
+            Some(_) => Err(Error::Population(PopulationError::DuplicateName(name.to_string()))),
+            None => Ok(()),
+        }
+    }
+
+    /// Creates `shape.len()` neurons of type `T` from `params` and registers them as a population,
+    /// e.g. `director.add_population::<LifNeuron>("hidden", Shape::D1(100), LifParams::new(0.9))`.
+    pub fn add_population<T>(&mut self, name: &str, shape: Shape, params: T::Params) -> Result<Population, Error>
+    where
+        T: CommonlyCreateable + Neuron + 'static,
+    {
+        let neurons = T::batch_create_new(shape.len(), params);
+        self.register_population(name, shape, neurons)
+    }
+
+    pub fn populations(&self) -> &[Population] {
+        &self.populations
+    }
+
+    pub fn population(&self, name: &str) -> Option<&Population> {
+        self.populations.iter().find(|population| population.name == name)
+    }
+
+    pub fn population_of(&self, id: NeuronUniqueId) -> Option<&Population> {
+        self.populations.iter().find(|population| population.contains(id))
+    }
+}
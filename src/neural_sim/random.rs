@@ -0,0 +1,101 @@
+/// Small in-crate pseudo random generator (xoshiro256** seeded through SplitMix64),
+/// so that every stochastic feature of the simulator is reproducible from a single `u64`.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: [u64; 4],
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+impl Rng {
+    pub fn seed_from_u64(seed: u64) -> Self {
+        let mut splitmix_state = seed;
+        let mut state = [0; 4];
+        for word in &mut state {
+            *word = splitmix64(&mut splitmix_state);
+        }
+        Self { state }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let result = self.state[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = self.state[1] << 17;
+
+        self.state[2] ^= self.state[0];
+        self.state[3] ^= self.state[1];
+        self.state[1] ^= self.state[2];
+        self.state[0] ^= self.state[3];
+        self.state[2] ^= t;
+        self.state[3] = self.state[3].rotate_left(45);
+
+        result
+    }
+
+    /// Uniform sample from `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+
+    pub fn uniform(&mut self, low: f32, high: f32) -> f32 {
+        (f64::from(low) + self.next_f64() * (f64::from(high) - f64::from(low))) as f32
+    }
+
+    /// Standard normal sample, Box-Muller transform.
+    pub fn standard_normal(&mut self) -> f64 {
+        let u1 = 1.0 - self.next_f64(); // (0, 1], keeps ln finite
+        let u2 = self.next_f64();
+        (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+    }
+
+    pub fn normal(&mut self, mean: f32, std_dev: f32) -> f32 {
+        (f64::from(mean) + f64::from(std_dev) * self.standard_normal()) as f32
+    }
+
+    /// Sample whose logarithm is normally distributed with `mu` and `sigma`.
+    pub fn lognormal(&mut self, mu: f32, sigma: f32) -> f32 {
+        (f64::from(mu) + f64::from(sigma) * self.standard_normal()).exp() as f32
+    }
+
+    /// `true` with probability `p`.
+    pub fn bernoulli(&mut self, p: f32) -> bool {
+        self.next_f64() < f64::from(p)
+    }
+}
+
+/// Seed used by a `Simulation` unless another one is set, so that runs are reproducible by default.
+pub const DEFAULT_SEED: u64 = 0;
+
+/// Independent random streams derived from a parent seed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stream {
+    /// Director with the given id.
+    Director(usize),
+    Neuron(u32),
+    /// The n-th `create_links_by_rule` call of a director.
+    Projection(u64),
+    /// Sub-stream of a projection or neuron, e.g. weights and connectivity of the same projection.
+    Sub(u64),
+}
+
+fn mix(value: u64) -> u64 {
+    let mut state = value;
+    splitmix64(&mut state)
+}
+
+/// Deterministically derives the seed of `stream` from `parent`. The derivation does not depend
+/// on the order streams are requested in, so adding a neuron does not shift every other stream.
+pub fn derive_seed(parent: u64, stream: Stream) -> u64 {
+    let (tag, index): (u64, u64) = match stream {
+        Stream::Director(index) => (1, index as u64),
+        Stream::Neuron(id) => (2, id.into()),
+        Stream::Projection(index) => (3, index),
+        Stream::Sub(index) => (4, index),
+    };
+    mix(mix(parent ^ tag.wrapping_mul(0xA24B_AED4_963E_E407)) ^ index)
+}
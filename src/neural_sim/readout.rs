@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use super::error::Error;
+use super::neuron::ResetMode;
+use super::population::Population;
+use super::random::Rng;
+use super::{NeuronUniqueId, Simulation};
+
+/// How the activity of the output population is turned into a class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReadoutMode {
+    /// Neuron with the most spikes during the sample.
+    #[default]
+    SpikeCount,
+    /// Neuron that spiked first.
+    FirstSpike,
+    /// Neuron with the highest membrane potential at the end of the sample.
+    MembranePotential,
+}
+
+/// What happens to network state between two samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleReset {
+    /// Carry potentials over to the next sample.
+    Keep,
+    Reset(ResetMode),
+    /// Let the network run silent for the given number of steps.
+    Settle(u32),
+}
+
+impl Default for SampleReset {
+    fn default() -> Self {
+        Self::Reset(ResetMode::State)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sample {
+    /// Spike times of every input neuron, relative to the start of the sample.
+    pub input: Vec<Vec<u32>>,
+    pub label: usize,
+}
+
+impl Sample {
+    /// Rate codes `values` in `[0, 1]` as Poisson spike trains: a value of 1 spikes with
+    /// probability `max_rate` at every one of `steps` steps.
+    pub fn poisson(values: &[f32], label: usize, steps: u32, max_rate: f32, rng: &mut Rng) -> Self {
+        let input = values
+            .iter()
+            .map(|value| {
+                let p = value.clamp(0., 1.) * max_rate;
+                (0..steps).filter(|_| rng.bernoulli(p)).collect()
+            })
+            .collect();
+        Self { input, label }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Evaluation {
+    /// `None` where the output population stayed silent.
+    pub predictions: Vec<Option<usize>>,
+    /// `confusion[label][predicted]`; silent samples are not counted in it.
+    pub confusion: Vec<Vec<usize>>,
+    pub accuracy: f32,
+}
+
+impl fmt::Display for Evaluation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let silent = self.predictions.iter().filter(|prediction| prediction.is_none()).count();
+        writeln!(f, "accuracy: {:.2}% of {} samples ({silent} silent)", self.accuracy * 100., self.predictions.len())?;
+        writeln!(f, "confusion (rows: label, columns: prediction):")?;
+        for row in &self.confusion {
+            let row: Vec<String> = row.iter().map(|count| format!("{count:>5}")).collect();
+            writeln!(f, "{}", row.join(""))?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs samples through one director of a simulation and reads classes from an output
+/// population, one class per output neuron.
+pub struct Classifier {
+    director: usize,
+    input: Population,
+    output: Population,
+    steps_per_sample: u32,
+    mode: ReadoutMode,
+    reset: SampleReset,
+}
+
+impl Classifier {
+    pub fn new(director: usize, input: Population, output: Population, steps_per_sample: u32) -> Self {
+        Self {
+            director,
+            input,
+            output,
+            steps_per_sample,
+            mode: ReadoutMode::default(),
+            reset: SampleReset::default(),
+        }
+    }
+
+    pub fn with_mode(self, mode: ReadoutMode) -> Self {
+        Self { mode, ..self }
+    }
+
+    pub fn with_reset(self, reset: SampleReset) -> Self {
+        Self { reset, ..self }
+    }
+
+    pub fn classes(&self) -> usize {
+        self.output.len()
+    }
+
+    /// Presents one sample starting at the current step and returns the predicted class.
+    pub fn predict(&self, sim: &mut Simulation, input: &[Vec<u32>]) -> Result<Option<usize>, Error> {
+        if input.len() != self.input.len() {
+            return Err(Error::SampleShape { expected: self.input.len(), found: input.len() });
+        }
+        let director = sim.director_mut(self.director).ok_or(Error::UnknownDirector(self.director))?;
+        match self.reset {
+            SampleReset::Keep => {}
+            SampleReset::Reset(mode) => director.reset(mode)?,
+            SampleReset::Settle(steps) => sim.run_for(steps)?,
+        }
+
+        let director = sim.director_mut(self.director).ok_or(Error::UnknownDirector(self.director))?;
+        let start = director.cur_time();
+        for (id, spike_times) in self.input.iter().zip(input) {
+            for time in spike_times.iter().filter(|time| **time < self.steps_per_sample) {
+                director.schedule_spike(*id, start + time)?;
+            }
+        }
+
+        let positions: HashMap<NeuronUniqueId, usize> =
+            self.output.iter().enumerate().map(|(position, id)| (*id, position)).collect();
+        let mut counts = vec![0usize; self.output.len()];
+        let mut first_spikes: Vec<Option<u32>> = vec![None; self.output.len()];
+        for step in 0..self.steps_per_sample {
+            sim.step()?;
+            for id in sim.directors()[self.director].fired_last_step() {
+                if let Some(position) = positions.get(id) {
+                    counts[*position] += 1;
+                    first_spikes[*position].get_or_insert(step);
+                }
+            }
+        }
+
+        let prediction = match self.mode {
+            ReadoutMode::SpikeCount => arg_max(counts.iter().map(|count| (*count > 0).then_some(*count as f32))),
+            ReadoutMode::FirstSpike => arg_max(first_spikes.iter().map(|first| first.map(|step| -(step as f32)))),
+            ReadoutMode::MembranePotential => {
+                let director = &sim.directors()[self.director];
+                let potentials = self
+                    .output
+                    .iter()
+                    .map(|id| director.potential(*id).map(Some))
+                    .collect::<Result<Vec<Option<f32>>, Error>>()?;
+                arg_max(potentials.into_iter())
+            }
+        };
+        Ok(prediction)
+    }
+
+    pub fn evaluate(&self, sim: &mut Simulation, dataset: &[Sample]) -> Result<Evaluation, Error> {
+        let classes = self.classes();
+        let mut confusion = vec![vec![0; classes]; classes];
+        let mut predictions = Vec::with_capacity(dataset.len());
+        let mut correct = 0;
+        for sample in dataset {
+            if sample.label >= classes {
+                return Err(Error::SampleShape { expected: classes, found: sample.label + 1 });
+            }
+            let prediction = self.predict(sim, &sample.input)?;
+            if let Some(predicted) = prediction {
+                confusion[sample.label][predicted] += 1;
+                if predicted == sample.label {
+                    correct += 1;
+                }
+            }
+            predictions.push(prediction);
+        }
+        let accuracy = if dataset.is_empty() { 0. } else { correct as f32 / dataset.len() as f32 };
+        Ok(Evaluation {
+            predictions,
+            confusion,
+            accuracy,
+        })
+    }
+}
+
+/// Position of the largest score, the first one on ties; `None` if nothing scored.
+fn arg_max(scores: impl Iterator<Item = Option<f32>>) -> Option<usize> {
+    let mut best: Option<(usize, f32)> = None;
+    for (position, score) in scores.enumerate() {
+        if let Some(score) = score
+            && best.is_none_or(|(_, best_score)| score > best_score)
+        {
+            best = Some((position, score));
+        }
+    }
+    best.map(|(position, _)| position)
+}
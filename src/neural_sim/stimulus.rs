@@ -0,0 +1,157 @@
+use std::ops::Range;
+
+use super::connectivity::InputBuffer;
+use super::error::Error;
+use super::vectorized::LifPopulationStore;
+use super::{Director, NeuronSlot, NeuronUniqueId, local_index};
+
+pub type StimulusCallback = Box<dyn FnMut(&mut NeuronControl) -> Result<(), Error>>;
+
+/// Something done to neurons at the start of a given time step, right after leak.
+pub enum Stimulus {
+    /// One step current pulse, integrated like synaptic input.
+    Pulse { ids: Vec<NeuronUniqueId>, amplitude: f32 },
+    /// Sets the membrane potential; a neuron set at or above its threshold fires at this step.
+    SetPotential { ids: Vec<NeuronUniqueId>, potential: f32 },
+    Callback(StimulusCallback),
+}
+
+/// Access to the neurons of a running director from a stimulus callback.
+pub struct NeuronControl<'a> {
+    pub(super) slots: &'a [NeuronSlot],
+    /// Id of the first slot, ids of other directors do not resolve.
+    pub(super) first_id: NeuronUniqueId,
+    pub(super) vectorized: &'a mut [LifPopulationStore],
+    pub(super) input: &'a mut InputBuffer,
+    pub(super) time_step: u32,
+}
+
+impl NeuronControl<'_> {
+    pub fn time_step(&self) -> u32 {
+        self.time_step
+    }
+
+    fn slot(&self, id: NeuronUniqueId) -> Result<&NeuronSlot, Error> {
+        local_index(self.first_id, id)
+            .and_then(|index| self.slots.get(index))
+            .ok_or(Error::UnknownNeuron(id))
+    }
+
+    pub fn potential(&self, id: NeuronUniqueId) -> Result<f32, Error> {
+        match self.slot(id)? {
+            NeuronSlot::Threaded(neuron) => Ok(neuron.lock()?.get_signal()),
+            NeuronSlot::Vectorized { store, index } => Ok(self.vectorized[*store].potentials()[*index]),
+        }
+    }
+
+    pub fn set_potential(&mut self, id: NeuronUniqueId, potential: f32) -> Result<(), Error> {
+        match self.slot(id)? {
+            NeuronSlot::Threaded(neuron) => {
+                let mut lock = neuron.lock()?;
+                lock.set_potential(potential);
+                lock.check_if_should_fire(self.time_step);
+            }
+            NeuronSlot::Vectorized { store, index } => {
+                let (store, index) = (*store, *index);
+                self.vectorized[store].set_potential(index, potential);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn threshold(&self, id: NeuronUniqueId) -> Result<f32, Error> {
+        match self.slot(id)? {
+            NeuronSlot::Threaded(neuron) => Ok(neuron.lock()?.threshold()),
+            NeuronSlot::Vectorized { store, index } => Ok(self.vectorized[*store].thresholds()[*index]),
+        }
+    }
+
+    pub fn set_threshold(&mut self, id: NeuronUniqueId, threshold: f32) -> Result<(), Error> {
+        match self.slot(id)? {
+            NeuronSlot::Threaded(neuron) => neuron.lock()?.set_threshold(threshold),
+            NeuronSlot::Vectorized { store, index } => {
+                let (store, index) = (*store, *index);
+                self.vectorized[store].set_threshold(index, threshold);
+            }
+        }
+        Ok(())
+    }
+
+    /// Input integrated at this step together with synaptic input.
+    pub fn add_input(&mut self, id: NeuronUniqueId, input: f32) -> Result<(), Error> {
+        self.slot(id)?;
+        self.input.add(id, input);
+        Ok(())
+    }
+}
+
+impl Director {
+    pub fn schedule_stimulus(&mut self, time_step: u32, stimulus: Stimulus) -> Result<(), Error> {
+        if time_step < self.cur_time {
+            return Err(Error::TimeStepInPast { requested: time_step, current: self.cur_time });
+        }
+        let ids = match &stimulus {
+            Stimulus::Pulse { ids, .. } | Stimulus::SetPotential { ids, .. } => ids.as_slice(),
+            Stimulus::Callback(_) => &[],
+        };
+        if let Some(id) = ids.iter().find(|id| !self.planner.is_booked(**id)) {
+            return Err(Error::UnknownNeuron(*id));
+        }
+        self.stimuli.entry(time_step).or_default().push(stimulus);
+        Ok(())
+    }
+
+    /// Current pulse of `amplitude` into every neuron of `ids` at each step of `steps`.
+    pub fn schedule_pulse(&mut self, ids: &[NeuronUniqueId], steps: Range<u32>, amplitude: f32) -> Result<(), Error> {
+        for time_step in steps {
+            self.schedule_stimulus(time_step, Stimulus::Pulse { ids: ids.to_vec(), amplitude })?;
+        }
+        Ok(())
+    }
+
+    /// Holds the potential of `ids` at `potential` at the start of each step of `steps`.
+    pub fn clamp_potential(&mut self, ids: &[NeuronUniqueId], steps: Range<u32>, potential: f32) -> Result<(), Error> {
+        for time_step in steps {
+            self.schedule_stimulus(time_step, Stimulus::SetPotential { ids: ids.to_vec(), potential })?;
+        }
+        Ok(())
+    }
+
+    /// Calls `callback` at the start of `time_step`, e.g. to change thresholds during a protocol.
+    pub fn schedule_callback<F>(&mut self, time_step: u32, callback: F) -> Result<(), Error>
+    where
+        F: FnMut(&mut NeuronControl) -> Result<(), Error> + 'static,
+    {
+        self.schedule_stimulus(time_step, Stimulus::Callback(Box::new(callback)))
+    }
+
+    /// Applies stimuli planned for the current step; returns whether there were any.
+    pub(super) fn apply_stimuli(&mut self) -> Result<bool, Error> {
+        let Some(stimuli) = self.stimuli.remove(&self.cur_time) else {
+            return Ok(false);
+        };
+        let mut control = NeuronControl {
+            slots: &self.slots,
+            first_id: self.planner.first_id,
+            vectorized: &mut self.vectorized,
+            input: &mut self.input_buffer,
+            time_step: self.cur_time,
+        };
+        for stimulus in stimuli {
+            match stimulus {
+                Stimulus::Pulse { ids, amplitude } => {
+                    for id in ids {
+                        control.add_input(id, amplitude)?;
+                    }
+                }
+                Stimulus::SetPotential { ids, potential } => {
+                    for id in ids {
+                        control.set_potential(id, potential)?;
+                    }
+                }
+                Stimulus::Callback(mut callback) => callback(&mut control)?,
+            }
+        }
+        Ok(true)
+    }
+}
@@ -0,0 +1,344 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use vcd_ng::{Command, IdCode, Parser, Value, VarType};
+
+use super::NeuronUniqueId;
+use super::error::Error;
+
+/// Identifies a traced neuron: director scope name and neuron id.
+pub type TraceKey = (String, NeuronUniqueId);
+
+/// Everything traced for one neuron.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct NeuronTrace {
+    pub population: Option<String>,
+    /// `(time, potential)` at the end of every step the potential was written in; it holds
+    /// until the next entry.
+    pub potentials: Vec<(u64, f64)>,
+    /// Steps the neuron fired at.
+    pub spikes: Vec<u64>,
+}
+
+impl NeuronTrace {
+    /// Potential held at `time`, `None` before the first entry.
+    pub fn potential_at(&self, time: u64) -> Option<f64> {
+        let position = self.potentials.partition_point(|(changed, _)| *changed <= time);
+        position.checked_sub(1).map(|position| self.potentials[position].1)
+    }
+
+    /// First time at or after which this trace and `other` disagree.
+    fn first_divergence(&self, other: &NeuronTrace, tolerance: f64) -> Option<(u64, DivergenceKind)> {
+        let mut times: Vec<u64> = self.potentials.iter().chain(&other.potentials).map(|(time, _)| *time).collect();
+        times.sort_unstable();
+        times.dedup();
+        let potential = times.into_iter().find_map(|time| {
+            let (expected, found) = (self.potential_at(time), other.potential_at(time));
+            let diverges = match (expected, found) {
+                (Some(expected), Some(found)) => (expected - found).abs() > tolerance || expected.is_nan() != found.is_nan(),
+                (None, None) => false,
+                _ => true,
+            };
+            diverges.then_some((time, DivergenceKind::Potential { expected, found }))
+        });
+        let spike = first_difference(&self.spikes, &other.spikes)
+            .map(|(time, expected)| (time, DivergenceKind::Spike { expected, found: !expected }));
+        match (potential, spike) {
+            (Some(potential), Some(spike)) => Some(if spike.0 <= potential.0 { spike } else { potential }),
+            (potential, spike) => potential.or(spike),
+        }
+    }
+}
+
+/// First time present in only one of two sorted lists, with whether `expected` has it.
+fn first_difference(expected: &[u64], found: &[u64]) -> Option<(u64, bool)> {
+    let (mut i, mut j) = (0, 0);
+    loop {
+        match (expected.get(i), found.get(j)) {
+            (Some(a), Some(b)) if a == b => (i, j) = (i + 1, j + 1),
+            (Some(a), Some(b)) => return Some(if a < b { (*a, true) } else { (*b, false) }),
+            (Some(a), None) => return Some((*a, true)),
+            (None, Some(b)) => return Some((*b, false)),
+            (None, None) => return None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DivergenceKind {
+    /// Neuron is traced in only one of the traces.
+    Missing { in_expected: bool },
+    Potential { expected: Option<f64>, found: Option<f64> },
+    Spike { expected: bool, found: bool },
+}
+
+/// Earliest point where two traces disagree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    pub time: u64,
+    pub director: String,
+    pub id: NeuronUniqueId,
+    pub kind: DivergenceKind,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let optional = |value: &Option<f64>| value.map_or("nothing".to_string(), |value| value.to_string());
+        write!(f, "at time {} neuron {} of director {}: ", self.time, self.id, self.director)?;
+        match &self.kind {
+            DivergenceKind::Missing { in_expected: true } => write!(f, "traced only in the expected trace"),
+            DivergenceKind::Missing { in_expected: false } => write!(f, "traced only in the found trace"),
+            DivergenceKind::Potential { expected, found } => {
+                write!(f, "potential {} expected, {} found", optional(expected), optional(found))
+            }
+            DivergenceKind::Spike { expected: true, .. } => write!(f, "spike expected, none found"),
+            DivergenceKind::Spike { .. } => write!(f, "unexpected spike"),
+        }
+    }
+}
+
+/// VCD trace written by `Simulation`, read back into per-neuron potentials and spikes.
+///
+/// Potentials are written after leak in every delta cycle of a step; only the last value of a
+/// step is kept, which is the potential the step ended with.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Trace {
+    seed: Option<u64>,
+    end_time: u64,
+    neurons: BTreeMap<TraceKey, NeuronTrace>,
+}
+
+enum Signal {
+    Potential(TraceKey),
+    Spike(TraceKey),
+}
+
+impl Trace {
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::parse(BufReader::new(File::open(path)?))
+    }
+
+    pub fn parse(reader: impl Read) -> Result<Self, Error> {
+        let mut trace = Trace::default();
+        let mut scopes: Vec<String> = Vec::new();
+        let mut signals: HashMap<IdCode, Signal> = HashMap::new();
+        let mut time = 0;
+
+        for command in Parser::new(reader) {
+            match command? {
+                Command::ScopeDef(_, name) => scopes.push(name.to_string()),
+                Command::Upscope => {
+                    scopes.pop();
+                }
+                Command::Comment(comment) => {
+                    if let Some(seed) = comment.trim().strip_prefix("seed ").and_then(|seed| seed.trim().parse().ok()) {
+                        trace.seed = Some(seed);
+                    }
+                }
+                Command::VarDef(var_type, _, code, reference, _) => {
+                    /* scopes are sim / director / population */
+                    let director = scopes.get(1).cloned().unwrap_or_default();
+                    let population = scopes.get(2).cloned();
+                    let (is_spike, id) = match (var_type, reference.strip_suffix("_spike")) {
+                        (VarType::Wire, Some(id)) => (true, id),
+                        (VarType::Real, None) => (false, reference.as_str()),
+                        _ => continue,
+                    };
+                    let id: NeuronUniqueId = id.parse().map_err(|_| Error::InvalidTrace("variable is not named after a neuron id"))?;
+                    trace.neurons.entry((director.clone(), id)).or_default().population = population;
+                    let key = (director, id);
+                    signals.insert(code, if is_spike { Signal::Spike(key) } else { Signal::Potential(key) });
+                }
+                Command::Timestamp(timestamp) => {
+                    time = timestamp;
+                    trace.end_time = trace.end_time.max(time);
+                }
+                Command::ChangeReal(code, value) => {
+                    if let Some(Signal::Potential(key)) = signals.get(&code) {
+                        let potentials = &mut trace.neurons.get_mut(key).unwrap().potentials;
+                        match potentials.last_mut() {
+                            Some((last, potential)) if *last == time => *potential = value,
+                            _ => potentials.push((time, value)),
+                        }
+                    }
+                }
+                Command::ChangeScalar(code, Value::V1) => {
+                    if let Some(Signal::Spike(key)) = signals.get(&code) {
+                        let spikes = &mut trace.neurons.get_mut(key).unwrap().spikes;
+                        if spikes.last() != Some(&time) {
+                            spikes.push(time);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(trace)
+    }
+
+    /// Master seed of the simulation that wrote the trace.
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /// Last timestamp in the trace.
+    pub fn end_time(&self) -> u64 {
+        self.end_time
+    }
+
+    pub fn neurons(&self) -> &BTreeMap<TraceKey, NeuronTrace> {
+        &self.neurons
+    }
+
+    pub fn neuron(&self, director: &str, id: NeuronUniqueId) -> Option<&NeuronTrace> {
+        self.neurons.get(&(director.to_string(), id))
+    }
+
+    /// All spikes as `(neuron_id, time)` pairs ordered by time, for one director.
+    pub fn spikes(&self, director: &str) -> Vec<(NeuronUniqueId, u64)> {
+        let mut spikes: Vec<(NeuronUniqueId, u64)> = self
+            .neurons
+            .iter()
+            .filter(|((name, _), _)| name == director)
+            .flat_map(|((_, id), neuron)| neuron.spikes.iter().map(move |time| (*id, *time)))
+            .collect();
+        spikes.sort_by_key(|(id, time)| (*time, *id));
+        spikes
+    }
+
+    /// Compares this, expected, trace with `found`; potentials may differ by `tolerance`.
+    /// Returns the earliest divergence, ties going to the lower director and neuron.
+    pub fn diff(&self, found: &Trace, tolerance: f64) -> Option<Divergence> {
+        let mut first: Option<Divergence> = None;
+        let keys = self.neurons.keys().chain(found.neurons.keys().filter(|key| !self.neurons.contains_key(*key)));
+        for key in keys {
+            let divergence = match (self.neurons.get(key), found.neurons.get(key)) {
+                (Some(expected), Some(found)) => expected.first_divergence(found, tolerance),
+                (expected, _) => Some((0, DivergenceKind::Missing { in_expected: expected.is_some() })),
+            };
+            let Some((time, kind)) = divergence else {
+                continue;
+            };
+            let earlier = first.as_ref().is_none_or(|first| (time, &key.0, key.1) < (first.time, &first.director, first.id));
+            if earlier {
+                first = Some(Divergence { time, director: key.0.clone(), id: key.1, kind });
+            }
+        }
+        first
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::neural_sim::current::CurrentSource;
+    use crate::neural_sim::neuron::lif_neuron::LifParams;
+    use crate::neural_sim::population::Shape;
+    use crate::neural_sim::{Director, Simulation};
+
+    fn neuron(potentials: &[(u64, f64)], spikes: &[u64]) -> NeuronTrace {
+        NeuronTrace { population: Some("n".to_string()), potentials: potentials.to_vec(), spikes: spikes.to_vec() }
+    }
+
+    fn trace(neurons: Vec<(NeuronUniqueId, NeuronTrace)>) -> Trace {
+        let end_time = neurons.iter().flat_map(|(_, neuron)| neuron.potentials.iter().map(|(time, _)| *time)).max().unwrap_or(0);
+        Trace {
+            seed: Some(0),
+            end_time,
+            neurons: neurons.into_iter().map(|(id, neuron)| (("0".to_string(), id), neuron)).collect(),
+        }
+    }
+
+    fn expected() -> Trace {
+        trace(vec![(0, neuron(&[(0, 0.3), (1, 0.6), (2, 0.), (3, 0.3)], &[2])), (1, neuron(&[(0, 0.), (3, 0.5)], &[]))])
+    }
+
+    #[test]
+    fn written_trace_is_parsed() {
+        let path = std::env::temp_dir().join(format!("trace_unit_{}.vcd", std::process::id()));
+        {
+            let mut sim = Simulation::new(true, path.to_str()).unwrap();
+            let director = sim.register_director(Director::new(10, 0).unwrap()).unwrap();
+            let neurons = director.add_vectorized_population("n", Shape::D1(2), LifParams::new(1.)).unwrap();
+            director.inject_current(&neurons[0..1], CurrentSource::Bias(0.3)).unwrap();
+            sim.start().unwrap();
+        }
+        let trace = Trace::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(trace.seed(), Some(0));
+        assert_eq!(trace.end_time(), 10);
+        assert_eq!(trace.neurons().len(), 2);
+        let driven = trace.neuron("0", 0).unwrap();
+        assert_eq!(driven.population.as_deref(), Some("n"));
+        assert_eq!(driven.spikes, [3, 7]);
+        assert!((driven.potential_at(2).unwrap() - 0.9).abs() < 1e-6);
+        assert_eq!(driven.potential_at(3), Some(0.));
+        assert_eq!(trace.neuron("0", 1).unwrap().potential_at(9), Some(0.));
+        assert_eq!(trace.spikes("0"), [(0, 3), (0, 7)]);
+        assert!(trace.neuron("1", 0).is_none());
+        assert_eq!(trace.diff(&trace, 0.), None);
+    }
+
+    #[test]
+    fn variables_must_be_named_after_neurons() {
+        let vcd = "$scope module sim $end\n$scope module 0 $end\n$var real 4 ! potential $end\n$upscope $end\n$upscope $end\n$enddefinitions $end\n";
+        assert!(matches!(Trace::parse(vcd.as_bytes()), Err(Error::InvalidTrace(_))));
+    }
+
+    #[test]
+    fn potentials_within_tolerance_match() {
+        let mut found = expected();
+        found.neurons.get_mut(&("0".to_string(), 0)).unwrap().potentials[1].1 += 1e-4;
+        assert_eq!(expected().diff(&found, 1e-3), None);
+
+        let divergence = expected().diff(&found, 1e-5).unwrap();
+        assert_eq!((divergence.time, divergence.director.as_str(), divergence.id), (1, "0", 0));
+        let DivergenceKind::Potential { expected: Some(expected), found: Some(found) } = divergence.kind else {
+            panic!("unexpected divergence {divergence}");
+        };
+        assert_eq!(expected, 0.6);
+        assert!((found - 0.6001).abs() < 1e-9);
+    }
+
+    #[test]
+    fn earliest_divergence_is_reported() {
+        let mut found = expected();
+        found.neurons.get_mut(&("0".to_string(), 1)).unwrap().potentials[1].1 = 0.4;
+        found.neurons.get_mut(&("0".to_string(), 0)).unwrap().spikes = vec![1, 2];
+        let divergence = expected().diff(&found, 1e-6).unwrap();
+        assert_eq!((divergence.time, divergence.id), (1, 0));
+        assert_eq!(divergence.kind, DivergenceKind::Spike { expected: false, found: true });
+        assert_eq!(divergence.to_string(), "at time 1 neuron 0 of director 0: unexpected spike");
+
+        found.neurons.get_mut(&("0".to_string(), 0)).unwrap().spikes = vec![];
+        let divergence = expected().diff(&found, 1e-6).unwrap();
+        assert_eq!((divergence.time, divergence.id), (2, 0));
+        assert_eq!(divergence.kind, DivergenceKind::Spike { expected: true, found: false });
+
+        let late = trace(vec![(0, neuron(&[(1, 0.6), (2, 0.), (3, 0.3)], &[2])), (1, neuron(&[(0, 0.), (3, 0.5)], &[]))]);
+        let divergence = expected().diff(&late, 1e-6).unwrap();
+        assert_eq!(divergence.kind, DivergenceKind::Potential { expected: Some(0.3), found: None });
+    }
+
+    #[test]
+    fn mismatched_signal_sets_diverge() {
+        let mut extra = expected();
+        extra.neurons.insert(("1".to_string(), 0), neuron(&[(0, 0.)], &[]));
+        let divergence = expected().diff(&extra, 1e-6).unwrap();
+        assert_eq!((divergence.time, divergence.director.as_str(), divergence.id), (0, "1", 0));
+        assert_eq!(divergence.kind, DivergenceKind::Missing { in_expected: false });
+
+        let divergence = extra.diff(&expected(), 1e-6).unwrap();
+        assert_eq!(divergence.kind, DivergenceKind::Missing { in_expected: true });
+        assert_eq!(divergence.to_string(), "at time 0 neuron 0 of director 1: traced only in the expected trace");
+
+        let mut missing = expected();
+        missing.neurons.remove(&("0".to_string(), 1));
+        let divergence = expected().diff(&missing, 1e-6).unwrap();
+        assert_eq!((divergence.id, divergence.kind), (1, DivergenceKind::Missing { in_expected: true }));
+    }
+}
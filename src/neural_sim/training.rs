@@ -0,0 +1,479 @@
+use std::collections::HashMap;
+
+use super::error::Error;
+use super::population::Population;
+use super::random::Rng;
+use super::readout::Sample;
+use super::{Director, NeuronUniqueId};
+
+/// Smooth stand-in for the derivative of the Heaviside spike function.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Surrogate {
+    /// `1 / (1 + slope * |x|)^2`
+    FastSigmoid { slope: f32 },
+    /// `(alpha / 2) / (1 + (pi / 2 * alpha * x)^2)`
+    ArcTan { alpha: f32 },
+}
+
+impl Surrogate {
+    fn derivative(&self, x: f32) -> f32 {
+        match *self {
+            Self::FastSigmoid { slope } => 1. / (1. + slope * x.abs()).powi(2),
+            Self::ArcTan { alpha } => {
+                let scaled = std::f32::consts::FRAC_PI_2 * alpha * x;
+                alpha / 2. / (1. + scaled * scaled)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrainingConfig {
+    pub epochs: usize,
+    pub batch_size: usize,
+    pub learning_rate: f32,
+    /// Steps every sample is presented for.
+    pub steps: u32,
+    pub surrogate: Surrogate,
+    /// Seeds shuffling of the dataset between epochs.
+    pub seed: u64,
+}
+
+impl Default for TrainingConfig {
+    fn default() -> Self {
+        Self {
+            epochs: 10,
+            batch_size: 16,
+            learning_rate: 1e-2,
+            steps: 25,
+            surrogate: Surrogate::FastSigmoid { slope: 10. },
+            seed: 0,
+        }
+    }
+}
+
+struct Layer {
+    ids: Vec<NeuronUniqueId>,
+    betas: Vec<f32>,
+    thresholds: Vec<f32>,
+}
+
+/// Dense view of the links from one layer to another; only existing links are trained.
+struct Projection {
+    source: usize,
+    target: usize,
+    columns: usize,
+    weights: Vec<f32>,
+    mask: Vec<bool>,
+}
+
+impl Projection {
+    /// Links onto the same or an earlier layer, delivered one step after their spikes.
+    fn recurrent(&self) -> bool {
+        self.source >= self.target
+    }
+}
+
+struct Adam {
+    m: Vec<f32>,
+    v: Vec<f32>,
+    t: i32,
+}
+
+impl Adam {
+    const BETA1: f32 = 0.9;
+    const BETA2: f32 = 0.999;
+    const EPSILON: f32 = 1e-8;
+
+    fn new(size: usize) -> Self {
+        Self { m: vec![0.; size], v: vec![0.; size], t: 0 }
+    }
+
+    fn step(&mut self, weights: &mut [f32], gradients: &[f32], mask: &[bool], learning_rate: f32) {
+        self.t += 1;
+        let m_correction = 1. - Self::BETA1.powi(self.t);
+        let v_correction = 1. - Self::BETA2.powi(self.t);
+        for index in 0..weights.len() {
+            if !mask[index] {
+                continue;
+            }
+            let gradient = gradients[index];
+            self.m[index] = Self::BETA1 * self.m[index] + (1. - Self::BETA1) * gradient;
+            self.v[index] = Self::BETA2 * self.v[index] + (1. - Self::BETA2) * gradient * gradient;
+            let m_hat = self.m[index] / m_correction;
+            let v_hat = self.v[index] / v_correction;
+            weights[index] -= learning_rate * m_hat / (v_hat.sqrt() + Self::EPSILON);
+        }
+    }
+}
+
+/// `[layer][step][neuron]` membrane values before reset and spikes of one presented sample.
+struct Trace {
+    potentials: Vec<Vec<Vec<f32>>>,
+    spikes: Vec<Vec<Vec<f32>>>,
+}
+
+/// Differentiable copy of LIF populations of a director, trained offline with
+/// backpropagation through time and a surrogate spike derivative.
+///
+/// The first population is the input: its spikes come straight from samples. The last one is
+/// the output, read out by spike count with a softmax cross-entropy loss. Each step a neuron
+/// leaks, integrates input and fires with a reset to zero like `LifNeuron` does. The reset is
+/// detached from the gradient.
+///
+/// Links from a population to a later one deliver spikes at the step they are fired, as the
+/// director does. Recurrent links, onto the same population or an earlier one, carry the
+/// spikes of the previous step instead: the director delivers them in later delta cycles of
+/// the same step, where a neuron may fire more than once, which has no differentiable
+/// counterpart. Weights of recurrent links are therefore trained for a one step delay.
+pub struct SurrogateNetwork {
+    layers: Vec<Layer>,
+    projections: Vec<Projection>,
+    /// Replaces spikes by the fast sigmoid of this slope and drops the reset, which makes the
+    /// network differentiable for finite-difference checks.
+    #[cfg(test)]
+    smooth: Option<f32>,
+}
+
+impl SurrogateNetwork {
+    pub fn from_director(director: &Director, populations: &[Population]) -> Result<Self, Error> {
+        if populations.len() < 2 {
+            return Err(Error::Training("at least an input and an output population are needed"));
+        }
+        let mut positions: HashMap<NeuronUniqueId, (usize, usize)> = HashMap::new();
+        let mut layers = Vec::with_capacity(populations.len());
+        for (layer, population) in populations.iter().enumerate() {
+            if population.kind() != "lif" {
+                return Err(Error::Training("only lif populations can be trained"));
+            }
+            let mut betas = Vec::with_capacity(population.len());
+            let mut thresholds = Vec::with_capacity(population.len());
+            for (position, id) in population.iter().enumerate() {
+                let info = director.neuron_info(*id)?;
+                let parameter = |name: &str| info.parameters.iter().find(|(key, _)| *key == name).map(|(_, value)| *value);
+                betas.push(parameter("beta").ok_or(Error::Training("neuron has no beta parameter"))?);
+                thresholds.push(parameter("threshold").ok_or(Error::Training("neuron has no threshold parameter"))?);
+                positions.insert(*id, (layer, position));
+            }
+            layers.push(Layer { ids: population.to_vec(), betas, thresholds });
+        }
+
+        let mut projections: Vec<Projection> = Vec::new();
+        for link in director.links() {
+            let (Some(&(source, row)), Some(&(target, column))) = (positions.get(&link.source), positions.get(&link.destination)) else {
+                continue;
+            };
+            if target == 0 {
+                return Err(Error::Training("input population can not receive links"));
+            }
+            let projection = match projections.iter_mut().position(|p| p.source == source && p.target == target) {
+                Some(index) => &mut projections[index],
+                None => {
+                    let size = layers[source].ids.len() * layers[target].ids.len();
+                    projections.push(Projection {
+                        source,
+                        target,
+                        columns: layers[target].ids.len(),
+                        weights: vec![0.; size],
+                        mask: vec![false; size],
+                    });
+                    projections.last_mut().unwrap()
+                }
+            };
+            let index = row * projection.columns + column;
+            if projection.mask[index] {
+                return Err(Error::Training("multapses can not be trained"));
+            }
+            projection.mask[index] = true;
+            projection.weights[index] = link.weight;
+        }
+        projections.sort_by_key(|projection| (projection.target, projection.source));
+        Ok(Self {
+            layers,
+            projections,
+            #[cfg(test)]
+            smooth: None,
+        })
+    }
+
+    #[cfg(test)]
+    fn smooth(&self) -> Option<f32> {
+        self.smooth
+    }
+
+    #[cfg(not(test))]
+    fn smooth(&self) -> Option<f32> {
+        None
+    }
+
+    fn forward(&self, input: &[Vec<u32>], steps: usize) -> Result<Trace, Error> {
+        if input.len() != self.layers[0].ids.len() {
+            return Err(Error::SampleShape { expected: self.layers[0].ids.len(), found: input.len() });
+        }
+        let mut potentials: Vec<Vec<Vec<f32>>> =
+            self.layers.iter().map(|layer| vec![vec![0.; layer.ids.len()]; steps]).collect();
+        let mut spikes = potentials.clone();
+        for (neuron, spike_times) in input.iter().enumerate() {
+            for time in spike_times.iter().map(|time| *time as usize).filter(|time| *time < steps) {
+                spikes[0][time][neuron] = 1.;
+            }
+        }
+
+        for t in 0..steps {
+            for (index, layer) in self.layers.iter().enumerate().skip(1) {
+                let mut current = vec![0.; layer.ids.len()];
+                for projection in self.projections.iter().filter(|projection| projection.target == index) {
+                    let source_step = match (projection.recurrent(), t) {
+                        (false, t) => t,
+                        (true, 0) => continue,
+                        (true, t) => t - 1,
+                    };
+                    for (row, spike) in spikes[projection.source][source_step].iter().enumerate() {
+                        if *spike == 0. {
+                            continue;
+                        }
+                        let weights = &projection.weights[row * projection.columns..(row + 1) * projection.columns];
+                        for (input, weight) in current.iter_mut().zip(weights) {
+                            *input += weight * spike;
+                        }
+                    }
+                }
+                for neuron in 0..layer.ids.len() {
+                    let previous = match (t, self.smooth()) {
+                        (0, _) => 0.,
+                        (t, Some(_)) => potentials[index][t - 1][neuron],
+                        (t, None) => potentials[index][t - 1][neuron] * (1. - spikes[index][t - 1][neuron]),
+                    };
+                    let potential = layer.betas[neuron] * previous + current[neuron];
+                    potentials[index][t][neuron] = potential;
+                    let x = potential - layer.thresholds[neuron];
+                    spikes[index][t][neuron] = match self.smooth() {
+                        Some(slope) => 0.5 + x / (1. + slope * x.abs()),
+                        None if x >= 0. => 1.,
+                        None => 0.,
+                    };
+                }
+            }
+        }
+        Ok(Trace { potentials, spikes })
+    }
+
+    fn output_counts(&self, trace: &Trace) -> Vec<f32> {
+        let output = self.layers.len() - 1;
+        let mut counts = vec![0.; self.layers[output].ids.len()];
+        for step in &trace.spikes[output] {
+            for (count, spike) in counts.iter_mut().zip(step) {
+                *count += spike;
+            }
+        }
+        counts
+    }
+
+    /// Class with the most output spikes, `None` if the output stays silent.
+    pub fn predict(&self, input: &[Vec<u32>], steps: u32) -> Result<Option<usize>, Error> {
+        let counts = self.output_counts(&self.forward(input, steps as usize)?);
+        let (best, count) = counts
+            .iter()
+            .enumerate()
+            .fold((0, 0.), |best, (index, count)| if *count > best.1 { (index, *count) } else { best });
+        Ok((count > 0.).then_some(best))
+    }
+
+    pub fn accuracy(&self, dataset: &[Sample], steps: u32) -> Result<f32, Error> {
+        let mut correct = 0;
+        for sample in dataset {
+            if self.predict(&sample.input, steps)? == Some(sample.label) {
+                correct += 1;
+            }
+        }
+        Ok(if dataset.is_empty() { 0. } else { correct as f32 / dataset.len() as f32 })
+    }
+
+    /// Adds the gradients of one sample to `gradients` and returns its loss.
+    fn backward(&self, sample: &Sample, config: &TrainingConfig, gradients: &mut [Vec<f32>]) -> Result<f32, Error> {
+        let steps = config.steps as usize;
+        let trace = self.forward(&sample.input, steps)?;
+        let output = self.layers.len() - 1;
+        if sample.label >= self.layers[output].ids.len() {
+            return Err(Error::SampleShape { expected: self.layers[output].ids.len(), found: sample.label + 1 });
+        }
+
+        /* softmax cross-entropy over output spike counts */
+        let counts = self.output_counts(&trace);
+        let max = counts.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+        let exps: Vec<f32> = counts.iter().map(|count| (count - max).exp()).collect();
+        let sum: f32 = exps.iter().sum();
+        let loss = -(exps[sample.label] / sum).ln();
+        let count_gradient: Vec<f32> = exps
+            .iter()
+            .enumerate()
+            .map(|(class, exp)| exp / sum - if class == sample.label { 1. } else { 0. })
+            .collect();
+
+        /* gradient of the loss with respect to potentials before reset, [layer][step][neuron] */
+        let mut potential_gradients: Vec<Vec<Vec<f32>>> =
+            self.layers.iter().map(|layer| vec![vec![0.; layer.ids.len()]; steps]).collect();
+        for t in (0..steps).rev() {
+            for index in (1..self.layers.len()).rev() {
+                let layer = &self.layers[index];
+                let mut spike_gradient = if index == output { count_gradient.clone() } else { vec![0.; layer.ids.len()] };
+                for projection in self.projections.iter().filter(|projection| projection.source == index) {
+                    let downstream = match (projection.recurrent(), t + 1 < steps) {
+                        (false, _) => &potential_gradients[projection.target][t],
+                        (true, true) => &potential_gradients[projection.target][t + 1],
+                        (true, false) => continue,
+                    };
+                    for (row, gradient) in spike_gradient.iter_mut().enumerate() {
+                        let weights = &projection.weights[row * projection.columns..(row + 1) * projection.columns];
+                        *gradient += weights.iter().zip(downstream).map(|(w, g)| w * g).sum::<f32>();
+                    }
+                }
+                for neuron in 0..layer.ids.len() {
+                    let potential = trace.potentials[index][t][neuron];
+                    let spike = trace.spikes[index][t][neuron];
+                    let kept = if self.smooth().is_some() { 1. } else { 1. - spike };
+                    let carried = if t + 1 < steps {
+                        layer.betas[neuron] * potential_gradients[index][t + 1][neuron] * kept
+                    } else {
+                        0.
+                    };
+                    potential_gradients[index][t][neuron] = spike_gradient[neuron]
+                        * config.surrogate.derivative(potential - layer.thresholds[neuron])
+                        + carried;
+                }
+            }
+        }
+
+        for (projection, gradient) in self.projections.iter().zip(gradients.iter_mut()) {
+            let delay = usize::from(projection.recurrent());
+            for (t, downstream) in potential_gradients[projection.target].iter().enumerate().skip(delay) {
+                for (row, spike) in trace.spikes[projection.source][t - delay].iter().enumerate() {
+                    if *spike == 0. {
+                        continue;
+                    }
+                    let row_gradient = &mut gradient[row * projection.columns..(row + 1) * projection.columns];
+                    for (weight_gradient, g) in row_gradient.iter_mut().zip(downstream) {
+                        *weight_gradient += g * spike;
+                    }
+                }
+            }
+        }
+        Ok(loss)
+    }
+
+    /// Trains the weights of every existing link between the populations; returns the mean
+    /// loss of every epoch.
+    pub fn train(&mut self, dataset: &[Sample], config: &TrainingConfig) -> Result<Vec<f32>, Error> {
+        if config.batch_size == 0 {
+            return Err(Error::Training("batch size must be positive"));
+        }
+        let mut optimizers: Vec<Adam> = self.projections.iter().map(|projection| Adam::new(projection.weights.len())).collect();
+        let mut rng = Rng::seed_from_u64(config.seed);
+        let mut order: Vec<usize> = (0..dataset.len()).collect();
+        let mut epoch_losses = Vec::with_capacity(config.epochs);
+
+        for _ in 0..config.epochs {
+            /* Fisher-Yates shuffle */
+            for i in (1..order.len()).rev() {
+                let j = (rng.next_u64() % (i as u64 + 1)) as usize;
+                order.swap(i, j);
+            }
+            let mut epoch_loss = 0.;
+            for batch in order.chunks(config.batch_size) {
+                let mut gradients: Vec<Vec<f32>> =
+                    self.projections.iter().map(|projection| vec![0.; projection.weights.len()]).collect();
+                for sample in batch {
+                    epoch_loss += self.backward(&dataset[*sample], config, &mut gradients)?;
+                }
+                let scale = 1. / batch.len() as f32;
+                for ((projection, gradient), optimizer) in self.projections.iter_mut().zip(&mut gradients).zip(&mut optimizers) {
+                    gradient.iter_mut().for_each(|g| *g *= scale);
+                    optimizer.step(&mut projection.weights, gradient, &projection.mask, config.learning_rate);
+                }
+            }
+            epoch_losses.push(if dataset.is_empty() { 0. } else { epoch_loss / dataset.len() as f32 });
+        }
+        Ok(epoch_losses)
+    }
+
+    /// Writes trained weights back into the links of `director`.
+    pub fn write_back(&self, director: &mut Director) -> Result<(), Error> {
+        for projection in &self.projections {
+            let sources = &self.layers[projection.source].ids;
+            let targets = &self.layers[projection.target].ids;
+            for (index, weight) in projection.weights.iter().enumerate() {
+                if projection.mask[index] {
+                    let (row, column) = (index / projection.columns, index % projection.columns);
+                    director.set_weight(sources[row], targets[column], *weight)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Input of 3 neurons feeding a hidden layer of 4 and an output of 2, with a skip
+    /// projection from input to output, recurrent hidden and output to hidden projections and
+    /// one missing link in each.
+    fn network(slope: f32) -> SurrogateNetwork {
+        let mut rng = Rng::seed_from_u64(7);
+        let layer = |size: usize, beta: f32| Layer {
+            ids: (0..size as NeuronUniqueId).collect(),
+            betas: vec![beta; size],
+            thresholds: vec![1.; size],
+        };
+        let mut projection = |source: usize, target: usize, rows: usize, columns: usize| {
+            let mut weights: Vec<f32> = (0..rows * columns).map(|_| rng.uniform(-0.5, 1.)).collect();
+            let mut mask = vec![true; rows * columns];
+            (weights[0], mask[0]) = (0., false);
+            Projection { source, target, columns, weights, mask }
+        };
+        let projections = vec![
+            projection(0, 1, 3, 4),
+            projection(1, 1, 4, 4),
+            projection(2, 1, 2, 4),
+            projection(0, 2, 3, 2),
+            projection(1, 2, 4, 2),
+        ];
+        SurrogateNetwork {
+            layers: vec![layer(3, 0.), layer(4, 0.8), layer(2, 0.6)],
+            projections,
+            smooth: Some(slope),
+        }
+    }
+
+    #[test]
+    fn gradients_match_finite_differences() {
+        let slope = 2.;
+        let config = TrainingConfig { steps: 6, surrogate: Surrogate::FastSigmoid { slope }, ..Default::default() };
+        let sample = Sample { input: vec![vec![0, 2, 3], vec![1, 4], vec![0, 5]], label: 1 };
+        let mut network = network(slope);
+        let mut gradients: Vec<Vec<f32>> = network.projections.iter().map(|p| vec![0.; p.weights.len()]).collect();
+        network.backward(&sample, &config, &mut gradients).unwrap();
+
+        let epsilon = 1e-2;
+        for (projection, analytic) in gradients.iter().enumerate() {
+            for (index, analytic) in analytic.iter().enumerate() {
+                if !network.projections[projection].mask[index] {
+                    continue;
+                }
+                let mut loss = |delta: f32| {
+                    network.projections[projection].weights[index] += delta;
+                    let mut scratch: Vec<Vec<f32>> = network.projections.iter().map(|p| vec![0.; p.weights.len()]).collect();
+                    let loss = network.backward(&sample, &config, &mut scratch).unwrap();
+                    network.projections[projection].weights[index] -= delta;
+                    loss
+                };
+                let numeric = (loss(epsilon) - loss(-epsilon)) / (2. * epsilon);
+                assert!(
+                    (numeric - analytic).abs() <= 1e-3 + 2e-2 * numeric.abs(),
+                    "projection {projection} weight {index}: backward gives {analytic}, finite differences {numeric}"
+                );
+            }
+        }
+    }
+}
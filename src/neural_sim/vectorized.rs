@@ -0,0 +1,185 @@
+use std::collections::BTreeMap;
+
+use vcd_ng::IdCode;
+
+use super::error::Error;
+use super::neuron::ResetMode;
+use super::neuron::lif_neuron::LifParams;
+use super::population::{Population, Shape};
+use super::{Director, NeuronUniqueId, SharedWriter};
+
+/// LIF population stored as a structure of arrays. Neurons do not get a thread or a lock
+/// of their own: the director leaks, integrates and checks thresholds of the whole
+/// population in tight loops on its own thread.
+pub struct LifPopulationStore {
+    first_id: NeuronUniqueId,
+    potentials: Vec<f32>,
+    thresholds: Vec<f32>,
+    betas: Vec<f32>,
+    last_leak_time: u32,
+    /// Time step -> indices of neurons that fire at it.
+    events: BTreeMap<u32, Vec<usize>>,
+    /// Neurons whose potential changed since the last threshold check.
+    touched: Vec<usize>,
+    wires: Vec<Option<IdCode>>,
+}
+
+impl LifPopulationStore {
+    fn new(first_id: NeuronUniqueId, size: usize, params: LifParams) -> Self {
+        Self {
+            first_id,
+            potentials: vec![0.; size],
+            thresholds: vec![params.threshold; size],
+            betas: vec![params.beta; size],
+            last_leak_time: 0,
+            events: BTreeMap::new(),
+            touched: Vec::new(),
+            wires: vec![None; size],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.potentials.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.potentials.is_empty()
+    }
+
+    pub fn index_of(&self, id: NeuronUniqueId) -> Option<usize> {
+        let index = id.checked_sub(self.first_id)? as usize;
+        (index < self.len()).then_some(index)
+    }
+
+    pub fn id_of(&self, index: usize) -> NeuronUniqueId {
+        self.first_id + index as NeuronUniqueId
+    }
+
+    pub fn potentials(&self) -> &[f32] {
+        &self.potentials
+    }
+
+    pub fn thresholds(&self) -> &[f32] {
+        &self.thresholds
+    }
+
+    pub fn betas(&self) -> &[f32] {
+        &self.betas
+    }
+
+    pub(super) fn set_wire(&mut self, index: usize, wire: IdCode) {
+        self.wires[index] = Some(wire);
+    }
+
+    /// Same contract as [`Leaky`](super::neuron::Leaky): leak once per step, before any input.
+    pub(super) fn leak(&mut self, time_step: u32) {
+        let dt = time_step.abs_diff(self.last_leak_time);
+        if dt != 0 {
+            let dt = dt as i32;
+            for (potential, beta) in self.potentials.iter_mut().zip(&self.betas) {
+                *potential *= beta.powi(dt);
+            }
+        }
+        self.last_leak_time = time_step;
+    }
+
+    pub(super) fn schedule(&mut self, index: usize, time_step: u32) {
+        self.events.entry(time_step).or_default().push(index);
+    }
+
+    pub(super) fn reset(&mut self, mode: ResetMode, time_step: u32) {
+        self.potentials.fill(0.);
+        self.touched.clear();
+        if mode != ResetMode::Potentials {
+            self.events.clear();
+            self.last_leak_time = time_step;
+        }
+    }
+
+    pub(super) fn set_potential(&mut self, index: usize, potential: f32) {
+        self.potentials[index] = potential;
+        self.touched.push(index);
+    }
+
+    /// Like `LifNeuron`, the new threshold is compared with the potential on the next input.
+    pub(super) fn set_threshold(&mut self, index: usize, threshold: f32) {
+        self.thresholds[index] = threshold;
+    }
+
+    pub(super) fn receive(&mut self, index: usize, signal: f32) {
+        self.potentials[index] += signal;
+        self.touched.push(index);
+    }
+
+    /// Neurons that received input or had their potential set and are at or above threshold
+    /// fire at `time_step` and are reset.
+    pub(super) fn check_thresholds(&mut self, time_step: u32) {
+        if self.touched.is_empty() {
+            return;
+        }
+        self.touched.sort_unstable();
+        self.touched.dedup();
+        let mut crossed = Vec::new();
+        for index in self.touched.drain(..) {
+            if self.potentials[index] >= self.thresholds[index] {
+                self.potentials[index] = 0.;
+                crossed.push(index);
+            }
+        }
+        if !crossed.is_empty() {
+            self.events.entry(time_step).or_default().extend(crossed);
+        }
+    }
+
+    /// Pops every event planned for `time_step` and appends the firing ids to `fired`.
+    pub(super) fn collect_fired(&mut self, time_step: u32, fired: &mut Vec<NeuronUniqueId>) {
+        if let Some(indices) = self.events.remove(&time_step) {
+            fired.extend(indices.into_iter().map(|index| self.id_of(index)));
+        }
+    }
+
+    pub(super) fn write_trace(&self, writer: &Option<SharedWriter>) -> Result<(), Error> {
+        if let Some(writer) = writer {
+            let mut lock = writer.lock()?;
+            for (wire, potential) in self.wires.iter().zip(&self.potentials) {
+                if let Some(wire) = wire {
+                    lock.change_real(*wire, (*potential).into())?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Director {
+    /// Registers a population of LIF neurons kept in a `LifPopulationStore` instead of
+    /// individual `LifNeuron` trait objects. Both kinds can be linked with each other freely.
+    pub fn add_vectorized_population(&mut self, name: &str, shape: Shape, params: LifParams) -> Result<Population, Error> {
+        if self.initialized {
+            return Err(Error::AlreadyRunning);
+        }
+        self.check_population_name(name)?;
+        let first_id = self.planner.next_available_id;
+        let ids: Vec<NeuronUniqueId> = (0..shape.len()).map(|_| self.planner.book_id()).collect::<Result<_, _>>()?;
+        let population = Population::new(name, shape, "lif", ids);
+        self.populations.push(population.clone());
+        self.vectorized.push(LifPopulationStore::new(first_id, shape.len(), params));
+        Ok(population)
+    }
+
+    pub fn vectorized_populations(&self) -> &[LifPopulationStore] {
+        &self.vectorized
+    }
+
+    pub(super) fn vectorized_slot(&self, id: NeuronUniqueId) -> Option<(usize, usize)> {
+        vectorized_slot(&self.vectorized, id)
+    }
+}
+
+/// Store index and position inside it of a vectorized neuron.
+pub(super) fn vectorized_slot(stores: &[LifPopulationStore], id: NeuronUniqueId) -> Option<(usize, usize)> {
+    stores
+        .iter()
+        .enumerate()
+        .find_map(|(store, population)| population.index_of(id).map(|index| (store, index)))
+}
@@ -0,0 +1,112 @@
+//! Spike train statistics and the `spike_report` tool built on them.
+
+use std::process::Command;
+
+use rust_nn_framewrk::neural_sim::analysis::{
+    SpikeTrains, read_spikes_csv, van_rossum_distance, victor_purpura_distance, write_spikes_csv,
+};
+use rust_nn_framewrk::neural_sim::error::Error;
+
+/// Neuron 1 fires every 10 steps from 0 on, neuron 2 two steps after it.
+fn regular_spikes() -> Vec<(u32, u32)> {
+    [0, 10, 20, 30].iter().flat_map(|time| [(1, *time), (2, time + 2)]).collect()
+}
+
+fn assert_close(found: f32, expected: f32) {
+    assert!((found - expected).abs() < 1e-5, "{found} is not {expected}");
+}
+
+#[test]
+fn rates_and_intervals() {
+    let trains = SpikeTrains::new(&regular_spikes(), 0..40).with_neurons(&[3]);
+    assert_eq!(trains.neurons().collect::<Vec<_>>(), [1, 2, 3]);
+    assert_eq!(trains.train(2), &[2, 12, 22, 32]);
+    assert_eq!(trains.spike_count(), 8);
+    assert_close(trains.rate(1), 0.1);
+    assert_close(trains.population_rate(), 8. / 120.);
+    let histogram = trains.population_rate_histogram(15);
+    assert_eq!(histogram.len(), 3);
+    assert_close(histogram[0], 4. / 45.);
+    assert_close(histogram[2], 2. / 30.);
+
+    assert_eq!(trains.inter_spike_intervals(1), [10, 10, 10]);
+    assert_eq!(trains.isi_histogram(5).counts, [0, 0, 6]);
+    assert_eq!(trains.cv(1), Some(0.));
+    assert_eq!(trains.cv(3), None);
+    assert_eq!(trains.fano_factor(1, 10), Some(0.));
+    assert_eq!(trains.fano_factor(3, 10), None);
+}
+
+#[test]
+fn window_drops_outside_spikes() {
+    let trains = SpikeTrains::new(&regular_spikes(), 10..30);
+    assert_eq!(trains.train(1), &[10, 20]);
+    assert_eq!(trains.binned_counts(2, 15), [2.]);
+}
+
+#[test]
+fn correlations_between_neurons() {
+    let trains = SpikeTrains::new(&regular_spikes(), 0..40);
+    assert_eq!(trains.cross_correlogram(1, 2, 3), [0, 0, 0, 0, 0, 4, 0]);
+    assert_eq!(trains.cross_correlogram(2, 1, 3), [0, 4, 0, 0, 0, 0, 0]);
+    assert_eq!(trains.correlation(1, 2, 10), None);
+
+    let bursts = SpikeTrains::new(&[(3, 0), (3, 1), (3, 20), (3, 21), (4, 2), (4, 22)], 0..40);
+    assert_close(bursts.correlation(3, 4, 10).unwrap(), 1.);
+    assert_close(bursts.synchrony(10).unwrap(), 0.9f32.sqrt());
+}
+
+#[test]
+fn cross_correlogram_handles_the_end_of_time() {
+    let end = u32::MAX - 1;
+    let trains = SpikeTrains::new(&[(1, end), (2, end - 1), (2, end)], 0..u32::MAX);
+    assert_eq!(trains.cross_correlogram(1, 2, 5), [0, 0, 0, 0, 1, 1, 0, 0, 0, 0, 0]);
+}
+
+#[test]
+fn spike_train_distances() {
+    assert_eq!(van_rossum_distance(&[1, 5], &[1, 5], 10.), 0.);
+    assert_close(van_rossum_distance(&[0], &[], 10.), 0.5f32.sqrt());
+    assert_close(victor_purpura_distance(&[0], &[3], 0.1), 0.3);
+    assert_close(victor_purpura_distance(&[0], &[3], 1.), 2.);
+    assert_close(victor_purpura_distance(&[0, 10], &[], 0.1), 2.);
+}
+
+#[test]
+fn csv_round_trip() {
+    let mut csv = Vec::new();
+    write_spikes_csv(&mut csv, &regular_spikes()).unwrap();
+    assert!(csv.starts_with(b"neuron_id,time_step\n"));
+    assert_eq!(read_spikes_csv(csv.as_slice()).unwrap(), regular_spikes());
+
+    let commented = "# recorded by hand\n\n1, 4\n2,7\n";
+    assert_eq!(read_spikes_csv(commented.as_bytes()).unwrap(), [(1, 4), (2, 7)]);
+    assert!(matches!(read_spikes_csv("neuron_id,time_step\n1,2\n1,x\n".as_bytes()), Err(Error::InvalidCsv { line: 3 })));
+}
+
+fn spike_report(name: &str, spikes: &[(u32, u32)], args: &[&str]) -> String {
+    let path = std::env::temp_dir().join(format!("spike_report_{name}_{}.csv", std::process::id()));
+    write_spikes_csv(std::fs::File::create(&path).unwrap(), spikes).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_spike_report")).arg(&path).args(args).output().unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn spike_report_summarizes_a_csv_file() {
+    let report = spike_report("regular", &regular_spikes(), &["--lag", "3"]);
+    assert!(report.contains("spikes: 8 of 2 neurons in steps 0..33"), "{report}");
+    assert!(report.contains("summed cross-correlogram (lag -3..=3): [0, 0, 0, 0, 0, 4, 0]"), "{report}");
+
+    let end = u32::MAX - 1;
+    let report = spike_report("late", &[(1, end - 1), (2, end)], &["--start", &(end - 50).to_string()]);
+    assert!(report.contains(&format!("steps {}..{}", end - 50, u32::MAX)), "{report}");
+}
+
+#[test]
+fn spike_report_rejects_bad_arguments() {
+    let output = Command::new(env!("CARGO_BIN_EXE_spike_report")).arg("--bin").output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("usage: spike_report"));
+}
@@ -0,0 +1,100 @@
+//! Prebuilt circuits wired around existing populations.
+
+use rust_nn_framewrk::neural_sim::circuit::{WinnerTakeAll, WtaMode};
+use rust_nn_framewrk::neural_sim::current::CurrentSource;
+use rust_nn_framewrk::neural_sim::error::Error;
+use rust_nn_framewrk::neural_sim::neuron::lif_neuron::LifParams;
+use rust_nn_framewrk::neural_sim::population::{Population, Shape};
+use rust_nn_framewrk::neural_sim::{Director, Simulation};
+
+/// Three excitatory neurons driven by decreasing bias currents, so the first one crosses its
+/// threshold first, at step 3.
+fn driven_population(director: &mut Director) -> Population {
+    let excitatory = director.add_vectorized_population("excitatory", Shape::D1(3), LifParams::new(1.)).unwrap();
+    for (id, amount) in excitatory.iter().zip([0.3, 0.2, 0.1]) {
+        director.inject_current(&[*id], CurrentSource::Bias(amount)).unwrap();
+    }
+    excitatory
+}
+
+/// Excitatory spikes of the first 20 steps of a winner-take-all circuit.
+fn winner_spikes(mode: WtaMode) -> Vec<(u32, u32)> {
+    let mut sim = Simulation::new(false, None).unwrap();
+    let director = sim.register_director(Director::new(100, 0).unwrap()).unwrap();
+    let excitatory = driven_population(director);
+    WinnerTakeAll::new(&excitatory, mode).with_inhibition(-2.).build(director, "inhibitory").unwrap();
+    director.record_spikes(&excitatory).unwrap();
+    sim.run_for(20).unwrap();
+    sim.directors()[0].recorded_spikes().to_vec()
+}
+
+#[test]
+fn lateral_inhibition_silences_all_but_the_winner() {
+    /* the winner's own partner leaves it alone, so it keeps its undisturbed rhythm */
+    assert_eq!(winner_spikes(WtaMode::Lateral { k: 1 }), [(0, 3), (0, 7), (0, 11), (0, 15), (0, 19)]);
+}
+
+#[test]
+fn pooled_inhibition_silences_the_whole_population() {
+    /* the pool inhibits the winner too, which delays its next spike */
+    assert_eq!(winner_spikes(WtaMode::Pooled { k: 1 }), [(0, 3), (0, 14)]);
+}
+
+#[test]
+fn non_negative_inhibition_is_rejected() {
+    for mode in [WtaMode::Lateral { k: 1 }, WtaMode::Pooled { k: 1 }] {
+        for inhibition in [0., 0.5] {
+            let mut director = Director::new(100, 0).unwrap();
+            let excitatory = driven_population(&mut director);
+            let circuit = WinnerTakeAll::new(&excitatory, mode).with_inhibition(inhibition);
+            assert!(matches!(circuit.build(&mut director, "inhibitory"), Err(Error::InvalidCircuit(_))));
+        }
+    }
+}
+
+/// Spike steps of four excitatory neurons that alone would fire every 2, 3, 5 and 7 steps,
+/// under k-winners-take-all.
+fn k_winner_spikes(k: usize) -> Vec<Vec<u32>> {
+    let mut sim = Simulation::new(false, None).unwrap();
+    let director = sim.register_director(Director::new(100, 0).unwrap()).unwrap();
+    let excitatory = director.add_vectorized_population("excitatory", Shape::D1(4), LifParams::new(1.)).unwrap();
+    for (id, amount) in excitatory.iter().zip([0.5, 0.45, 0.2, 0.15]) {
+        director.inject_current(&[*id], CurrentSource::Bias(amount)).unwrap();
+    }
+    /* leaky counters, so spikes of the last steps count towards the k */
+    WinnerTakeAll::new(&excitatory, WtaMode::Lateral { k })
+        .with_inhibition(-2.)
+        .with_beta(0.5)
+        .build(director, "inhibitory")
+        .unwrap();
+    director.record_spikes(&excitatory).unwrap();
+    sim.run_for(20).unwrap();
+
+    let spikes = sim.directors()[0].recorded_spikes();
+    excitatory
+        .iter()
+        .map(|neuron| spikes.iter().filter(|(id, _)| id == neuron).map(|(_, step)| *step).collect())
+        .collect()
+}
+
+#[test]
+fn k_winners_keep_firing() {
+    let undisturbed: [Vec<u32>; 3] = [(1..20).step_by(2).collect(), (2..20).step_by(3).collect(), (4..20).step_by(5).collect()];
+    for k in 1..=3 {
+        let spikes = k_winner_spikes(k);
+        for (neuron, spikes) in spikes.iter().enumerate() {
+            let expected = if neuron < k { undisturbed[neuron].clone() } else { Vec::new() };
+            assert_eq!(*spikes, expected, "k {k}, neuron {neuron}");
+        }
+    }
+}
+
+#[test]
+fn k_must_fit_the_population() {
+    for mode in [WtaMode::Lateral { k: 0 }, WtaMode::Lateral { k: 4 }, WtaMode::Pooled { k: 0 }, WtaMode::Pooled { k: 4 }] {
+        let mut director = Director::new(100, 0).unwrap();
+        let excitatory = driven_population(&mut director);
+        let circuit = WinnerTakeAll::new(&excitatory, mode);
+        assert!(matches!(circuit.build(&mut director, "inhibitory"), Err(Error::InvalidCircuit(_))), "{mode:?}");
+    }
+}
@@ -0,0 +1,22 @@
+//! Fixtures shared by the integration tests.
+
+use rust_nn_framewrk::neural_sim::error::Error;
+use rust_nn_framewrk::neural_sim::neuron::lif_neuron::{LifNeuron, LifParams};
+use rust_nn_framewrk::neural_sim::population::{Population, Shape};
+use rust_nn_framewrk::neural_sim::Director;
+
+/// Storage of a LIF population: one `LifNeuron` thread per neuron or a `LifPopulationStore`.
+#[derive(Debug, Clone, Copy)]
+pub enum Backend {
+    Threaded,
+    Vectorized,
+}
+
+pub const BACKENDS: [Backend; 2] = [Backend::Threaded, Backend::Vectorized];
+
+pub fn population(director: &mut Director, backend: Backend, name: &str, size: usize, params: LifParams) -> Result<Population, Error> {
+    match backend {
+        Backend::Threaded => director.add_population::<LifNeuron>(name, Shape::D1(size), params),
+        Backend::Vectorized => director.add_vectorized_population(name, Shape::D1(size), params),
+    }
+}
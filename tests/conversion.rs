@@ -0,0 +1,127 @@
+//! `.npy` parsing and ANN to SNN conversion.
+
+use rust_nn_framewrk::neural_sim::conversion::{AnnToSnn, NpyArray};
+use rust_nn_framewrk::neural_sim::error::Error;
+use rust_nn_framewrk::neural_sim::population::Shape;
+use rust_nn_framewrk::neural_sim::random::Rng;
+use rust_nn_framewrk::neural_sim::readout::{Classifier, Sample};
+use rust_nn_framewrk::neural_sim::{Director, Simulation};
+
+/// Version 1 `.npy` file holding `values` as little-endian `descr`, padded like NumPy does.
+fn npy(descr: &str, shape: &str, values: &[f64]) -> Vec<u8> {
+    let mut header = format!("{{'descr': '{descr}', 'fortran_order': False, 'shape': {shape}, }}");
+    while (10 + header.len() + 1) % 64 != 0 {
+        header.push(' ');
+    }
+    header.push('\n');
+    let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+    bytes.extend((header.len() as u16).to_le_bytes());
+    bytes.extend(header.as_bytes());
+    for value in values {
+        match descr {
+            "<f4" => bytes.extend((*value as f32).to_le_bytes()),
+            _ => bytes.extend(value.to_le_bytes()),
+        }
+    }
+    bytes
+}
+
+fn array(shape: &[usize], data: &[f32]) -> NpyArray {
+    NpyArray::new(shape.to_vec(), data.to_vec()).unwrap()
+}
+
+#[test]
+fn npy_arrays_round_trip() {
+    let values = [0.5, -1.25, 3., 0., 7.75, -2.];
+    for descr in ["<f4", "<f8"] {
+        let parsed = NpyArray::parse(&npy(descr, "(2, 3)", &values)).unwrap();
+        assert_eq!(parsed, array(&[2, 3], &[0.5, -1.25, 3., 0., 7.75, -2.]));
+    }
+    assert_eq!(NpyArray::parse(&npy("<f4", "(3,)", &[1., 2., 3.])).unwrap().shape(), &[3]);
+    assert_eq!(NpyArray::parse(&npy("<f4", "()", &[4.])).unwrap().data(), &[4.]);
+
+    let mut version_2 = npy("<f4", "(2,)", &[1., 2.]);
+    version_2[6] = 2;
+    version_2.splice(8..10, [version_2[8], version_2[9], 0, 0]);
+    assert_eq!(NpyArray::parse(&version_2).unwrap().data(), &[1., 2.]);
+}
+
+#[test]
+fn bad_npy_files_are_rejected() {
+    let valid = npy("<f4", "(2,)", &[1., 2.]);
+    let mut bad_magic = valid.clone();
+    bad_magic[1] = b'X';
+    let mut bad_version = valid.clone();
+    bad_version[6] = 9;
+    let mut fortran = valid.clone();
+    let position = fortran.windows(5).position(|window| window == b"False").unwrap();
+    fortran[position..position + 5].copy_from_slice(b"True ");
+    let mut long_header = valid.clone();
+    long_header[8..10].copy_from_slice(&u16::MAX.to_le_bytes());
+    let files = [
+        bad_magic,
+        bad_version,
+        long_header,
+        valid[..9].to_vec(),
+        valid[..valid.len() - 1].to_vec(),
+        npy(">f4", "(2,)", &[1., 2.]),
+        npy("<i4", "(2,)", &[1., 2.]),
+        npy("<f4", "(two,)", &[1., 2.]),
+        npy("<f4", "(4294967296, 4294967296, 4294967296)", &[]),
+        npy("<f8", "(2305843009213693952,)", &[]),
+        fortran,
+    ];
+    for (index, bytes) in files.iter().enumerate() {
+        assert!(matches!(NpyArray::parse(bytes), Err(Error::Npy(_))), "file {index} was accepted");
+    }
+    assert!(matches!(NpyArray::new(vec![usize::MAX, 2], Vec::new()), Err(Error::Npy(_))));
+}
+
+#[test]
+fn empty_bias_is_rejected() {
+    let weights = array(&[2, 3], &[0.; 6]);
+    let result = AnnToSnn::new(Shape::D1(3)).dense(weights, Some(array(&[0], &[])));
+    assert!(matches!(result, Err(Error::Conversion(_))));
+
+    let weights = array(&[0, 1, 1, 1], &[]);
+    let result = AnnToSnn::new(Shape::D3(1, 2, 2)).conv2d(weights, Some(array(&[0], &[])), 1, 0);
+    assert!(matches!(result, Err(Error::Conversion(_))));
+}
+
+/// Three inputs, a hidden layer of three and two classes: class 0 wins when the first input
+/// outweighs the last one.
+fn dense_net() -> AnnToSnn {
+    let hidden = array(&[3, 3], &[1., 0., -0.5, 0., 0.5, 0., -0.5, 0., 1.]);
+    let output = array(&[2, 3], &[1., 0.2, 0., 0., 0.2, 1.]);
+    AnnToSnn::new(Shape::D1(3))
+        .dense(hidden, Some(array(&[3], &[0., 0.1, 0.])))
+        .unwrap()
+        .dense(output, None)
+        .unwrap()
+}
+
+#[test]
+fn dense_net_is_converted() {
+    let inputs = [[0.9, 0.2, 0.1], [0.1, 0.5, 0.8], [0.7, 0.9, 0.2], [0.2, 0.1, 1.]];
+    let calibration: Vec<Vec<f32>> = inputs.iter().map(|input| input.to_vec()).collect();
+    let mut net = dense_net();
+    net.balance(&calibration).unwrap();
+
+    let mut director = Director::new(10, 0).unwrap();
+    let populations = net.build(&mut director, "net").unwrap();
+    let sizes: Vec<usize> = populations.iter().map(|population| population.len()).collect();
+    assert_eq!(sizes, [3, 3, 2]);
+    assert_eq!(director.vectorized_populations().len(), 3);
+
+    let mut sim = Simulation::new(false, None).unwrap();
+    sim.register_director(director).unwrap();
+    let classifier = Classifier::new(0, populations[0].clone(), populations[2].clone(), 200);
+    let mut rng = Rng::seed_from_u64(1);
+    for input in calibration {
+        let activations = net.activations(&input).unwrap();
+        let output = &activations[2];
+        let expected = if output[0] > output[1] { 0 } else { 1 };
+        let sample = Sample::poisson(&input, expected, 200, 1., &mut rng);
+        assert_eq!(classifier.predict(&mut sim, &sample.input).unwrap(), Some(expected), "input {input:?}");
+    }
+}
@@ -0,0 +1,108 @@
+//! Injected currents, observed through neurons that forget everything between steps.
+
+mod common;
+
+use rust_nn_framewrk::neural_sim::current::CurrentSource;
+use rust_nn_framewrk::neural_sim::error::Error;
+use rust_nn_framewrk::neural_sim::neuron::lif_neuron::LifParams;
+use rust_nn_framewrk::neural_sim::population::Shape;
+use rust_nn_framewrk::neural_sim::{Director, Simulation};
+
+use common::{BACKENDS, Backend, population};
+
+/// With `beta = 0` and an unreachable threshold the potential at every step is exactly the
+/// current injected at that step. Returns those currents for `steps` steps, per neuron.
+fn injected(backend: Backend, neurons: usize, seed: u64, source: CurrentSource, steps: u32) -> Vec<Vec<f32>> {
+    let mut sim = Simulation::new(false, None).unwrap();
+    sim.set_seed(seed);
+    let director = sim.register_director(Director::new(steps, 0).unwrap()).unwrap();
+    let probes = population(director, backend, "probes", neurons, LifParams::new(0.).with_threshold(f32::MAX)).unwrap();
+    director.inject_current(&probes, source).unwrap();
+    director.record_potentials(&probes).unwrap();
+    sim.run_for(steps).unwrap();
+    let recorded = sim.directors()[0].recorded_potentials();
+    probes
+        .iter()
+        .map(|id| recorded[id].iter().map(|(_, potential)| *potential).collect())
+        .collect()
+}
+
+fn assert_close(found: &[f32], expected: &[f32], context: &str) {
+    assert_eq!(found.len(), expected.len(), "{context}");
+    for (step, (found, expected)) in found.iter().zip(expected).enumerate() {
+        assert!((found - expected).abs() < 1e-6, "{context}: step {step} got {found}, expected {expected}");
+    }
+}
+
+#[test]
+fn ramps_follow_the_line_between_their_ends() {
+    for backend in BACKENDS {
+        let ramp = CurrentSource::Ramp { start: 2, end: 6, from: 1., to: -1. };
+        let expected: Vec<f32> = (0..9u32)
+            .map(|t| if (2..=6).contains(&t) { 1. - 2. * (t - 2) as f32 / 4. } else { 0. })
+            .collect();
+        assert_close(&injected(backend, 1, 0, ramp, 9)[0], &expected, &format!("{backend:?}"));
+
+        let point = CurrentSource::Ramp { start: 3, end: 3, from: 0.5, to: 2. };
+        assert_close(&injected(backend, 1, 0, point, 5)[0], &[0., 0., 0., 0.5, 0.], &format!("{backend:?}"));
+    }
+}
+
+#[test]
+fn steps_hold_each_amplitude_until_the_next() {
+    for backend in BACKENDS {
+        /* out of order on purpose, inject_current sorts them */
+        let steps = CurrentSource::Steps(vec![(5, -0.5), (2, 1.), (7, 0.)]);
+        let expected = [0., 0., 1., 1., 1., -0.5, -0.5, 0., 0.];
+        assert_close(&injected(backend, 1, 0, steps, 9)[0], &expected, &format!("{backend:?}"));
+        assert_close(&injected(backend, 1, 0, CurrentSource::Bias(0.25), 3)[0], &[0.25; 3], &format!("{backend:?}"));
+    }
+}
+
+#[test]
+fn invalid_sources_are_rejected() {
+    let mut director = Director::new(10, 0).unwrap();
+    let probe = director.add_vectorized_population("probe", Shape::D1(1), LifParams::new(0.)).unwrap();
+    let invalid = [
+        CurrentSource::Ramp { start: 3, end: 2, from: 0., to: 1. },
+        CurrentSource::WhiteNoise { mean: 0., std_dev: -1. },
+        CurrentSource::OrnsteinUhlenbeck { mean: 0., std_dev: 1., tau: 0. },
+    ];
+    for source in invalid {
+        assert!(matches!(director.inject_current(&probe, source.clone()), Err(Error::InvalidCurrent(_))), "{source:?}");
+    }
+    assert!(matches!(director.inject_current(&[probe[0] + 1], CurrentSource::Bias(1.)), Err(Error::UnknownNeuron(_))));
+}
+
+#[test]
+fn ornstein_uhlenbeck_noise_is_reproducible() {
+    let source = CurrentSource::OrnsteinUhlenbeck { mean: 0.5, std_dev: 0.2, tau: 5. };
+    for backend in BACKENDS {
+        let first = injected(backend, 2, 3, source.clone(), 50);
+        assert_eq!(injected(backend, 2, 3, source.clone(), 50), first, "{backend:?}");
+        assert_ne!(injected(backend, 2, 4, source.clone(), 50), first, "{backend:?}");
+        assert_ne!(first[0], first[1], "{backend:?}");
+    }
+}
+
+#[test]
+fn ornstein_uhlenbeck_noise_has_the_stationary_statistics() {
+    let (mean, std_dev, tau) = (0.5, 0.2, 5.);
+    let runs = injected(Backend::Vectorized, 20, 1, CurrentSource::OrnsteinUhlenbeck { mean, std_dev, tau }, 2000);
+    /* skip the start, every process begins at the mean */
+    let samples: Vec<f64> = runs.iter().flat_map(|run| run[50..].iter().map(|value| f64::from(*value))).collect();
+    let count = samples.len() as f64;
+    let sample_mean = samples.iter().sum::<f64>() / count;
+    let variance = samples.iter().map(|value| (value - sample_mean).powi(2)).sum::<f64>() / count;
+    assert!((sample_mean - 0.5).abs() < 0.01, "mean {sample_mean}");
+    assert!((variance / 0.04 - 1.).abs() < 0.1, "variance {variance}");
+
+    /* successive samples are correlated by exp(-1 / tau) */
+    let covariance = runs
+        .iter()
+        .flat_map(|run| run[50..].windows(2).map(|pair| (f64::from(pair[0]) - sample_mean) * (f64::from(pair[1]) - sample_mean)))
+        .sum::<f64>()
+        / (count - runs.len() as f64);
+    let correlation = covariance / variance;
+    assert!((correlation - (-1f64 / 5.).exp()).abs() < 0.03, "correlation {correlation}");
+}
@@ -0,0 +1,110 @@
+//! Golden-trace regression tests: small canonical networks run on every backend, and the VCD
+//! trace of every run is compared with `Trace::diff` against the one checked in under
+//! `tests/golden/`.
+//!
+//! After an intended change of dynamics, regenerate the files with
+//! `GOLDEN_UPDATE=1 cargo test --test golden` and review the diff.
+
+mod common;
+
+use std::path::PathBuf;
+
+use rust_nn_framewrk::neural_sim::current::CurrentSource;
+use rust_nn_framewrk::neural_sim::error::Error;
+use rust_nn_framewrk::neural_sim::neuron::lif_neuron::LifParams;
+use rust_nn_framewrk::neural_sim::trace::Trace;
+use rust_nn_framewrk::neural_sim::{BatchLinkingRule, ControllingUnit, Director, Simulation, VecOrValueFloat};
+
+use common::{BACKENDS, Backend, population};
+
+const STEPS: u32 = 30;
+const TOLERANCE: f64 = 1e-6;
+
+/// One neuron driven by a constant bias, firing periodically.
+fn single_lif(director: &mut Director, backend: Backend) -> Result<(), Error> {
+    let neuron = population(director, backend, "neuron", 1, LifParams::new(0.8))?;
+    director.inject_current(&neuron, CurrentSource::Bias(0.3))
+}
+
+/// Scheduled input spikes travelling down a chain whose links need two close spikes to pass.
+fn chain(director: &mut Director, backend: Backend) -> Result<(), Error> {
+    let input = population(director, backend, "input", 1, LifParams::new(0.9))?;
+    let chain = population(director, backend, "chain", 3, LifParams::new(0.7))?;
+    director.schedule_spikes(&input, &[1, 2, 6, 12, 13, 14])?;
+    director.create_links_by_rule(&input, &chain[0..1], VecOrValueFloat::Val(0.6), BatchLinkingRule::OneToOne)?;
+    director.create_links_by_rule(&chain[0..2], &chain[1..3], VecOrValueFloat::Val(1.2), BatchLinkingRule::OneToOne)
+}
+
+/// Sources spiking at different steps, summed by one target.
+fn fan_in(director: &mut Director, backend: Backend) -> Result<(), Error> {
+    let sources = population(director, backend, "sources", 4, LifParams::new(0.9))?;
+    let target = population(director, backend, "target", 1, LifParams::new(0.85))?;
+    for (index, id) in sources.iter().enumerate() {
+        let offset = index as u32;
+        director.schedule_spikes(&[*id], &[2 + offset, 10 + 2 * offset, 20])?;
+    }
+    director.create_links_by_rule(
+        &sources,
+        &target,
+        VecOrValueFloat::Vec(vec![vec![0.3], vec![0.4], vec![0.25], vec![0.5]]),
+        BatchLinkingRule::FullyConnected,
+    )
+}
+
+/// Excitatory neuron exciting an inhibitory one that feeds back onto it.
+fn recurrent_pair(director: &mut Director, backend: Backend) -> Result<(), Error> {
+    let excitatory = population(director, backend, "excitatory", 1, LifParams::new(0.9))?;
+    let inhibitory = population(director, backend, "inhibitory", 1, LifParams::new(0.95))?;
+    director.inject_current(&excitatory, CurrentSource::Bias(0.35))?;
+    director.create_links_by_rule(&excitatory, &inhibitory, VecOrValueFloat::Val(0.6), BatchLinkingRule::OneToOne)?;
+    director.create_links_by_rule(&inhibitory, &excitatory, VecOrValueFloat::Val(-0.8), BatchLinkingRule::OneToOne)
+}
+
+/// Runs the network built by `build` for `STEPS` steps and reads back the trace it wrote to `path`.
+fn run(build: fn(&mut Director, Backend) -> Result<(), Error>, backend: Backend, path: &PathBuf) -> Result<Trace, Error> {
+    {
+        let mut sim = Simulation::new(true, path.to_str())?;
+        let director = sim.register_director(Director::new(STEPS, 0).ok_or(Error::UnknownDirector(0))?)?;
+        build(director, backend)?;
+        sim.start()?;
+    }
+    Trace::read(path)
+}
+
+fn check(name: &str, build: fn(&mut Director, Backend) -> Result<(), Error>) {
+    let golden_path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden").join(format!("{name}.vcd"));
+    if std::env::var_os("GOLDEN_UPDATE").is_some() {
+        run(build, Backend::Threaded, &golden_path).unwrap();
+    }
+    let expected = Trace::read(&golden_path).unwrap();
+    assert!(!expected.spikes("0").is_empty(), "{name}: golden trace has no spikes");
+
+    for backend in BACKENDS {
+        let path = std::env::temp_dir().join(format!("golden_{name}_{backend:?}_{}.vcd", std::process::id()));
+        let found = run(build, backend, &path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        if let Some(divergence) = expected.diff(&found, TOLERANCE) {
+            panic!("{name}: trace of the {backend:?} backend diverges {divergence}");
+        }
+    }
+}
+
+#[test]
+fn golden_single_lif() {
+    check("single_lif", single_lif);
+}
+
+#[test]
+fn golden_chain() {
+    check("chain", chain);
+}
+
+#[test]
+fn golden_fan_in() {
+    check("fan_in", fan_in);
+}
+
+#[test]
+fn golden_recurrent_pair() {
+    check("recurrent_pair", recurrent_pair);
+}
@@ -0,0 +1,89 @@
+//! Queries on the neurons and links a director holds.
+
+use rust_nn_framewrk::neural_sim::error::Error;
+use rust_nn_framewrk::neural_sim::introspection::Link;
+use rust_nn_framewrk::neural_sim::neuron::lif_neuron::{LifNeuron, LifParams};
+use rust_nn_framewrk::neural_sim::population::{Population, Shape};
+use rust_nn_framewrk::neural_sim::{ControllingUnit, Director, Simulation};
+
+/// Two threaded neurons `a` projecting onto three vectorized ones `b`, which link among themselves.
+fn network(director: &mut Director) -> (Population, Population) {
+    let a = director.add_population::<LifNeuron>("a", Shape::D1(2), LifParams::new(0.6)).unwrap();
+    let b = director.add_vectorized_population("b", Shape::D1(3), LifParams::new(0.8).with_threshold(2.)).unwrap();
+    director.create_link(a[0], b[0], 0.5).unwrap();
+    director.create_link(a[0], b[1], 0.25).unwrap();
+    director.create_link(a[1], b[1], -1.).unwrap();
+    director.create_link(b[0], b[2], 0.1).unwrap();
+    director.create_link(b[2], b[2], 0.3).unwrap();
+    (a, b)
+}
+
+fn link(source: u32, destination: u32, weight: f32) -> Link {
+    Link { source, destination, weight }
+}
+
+#[test]
+fn neuron_info_describes_both_backends() {
+    let mut director = Director::new(10, 0).unwrap();
+    let (a, b) = network(&mut director);
+    assert_eq!(director.neuron_ids(), [a[0], a[1], b[0], b[1], b[2]]);
+
+    let threaded = director.neuron_info(a[1]).unwrap();
+    assert_eq!((threaded.id, threaded.kind), (a[1], "lif"));
+    assert_eq!(threaded.parameters, [("beta", 0.6), ("threshold", 1.)]);
+    let vectorized = director.neuron_info(b[2]).unwrap();
+    assert_eq!((vectorized.id, vectorized.kind), (b[2], "lif"));
+    assert_eq!(vectorized.parameters, [("beta", 0.8), ("threshold", 2.)]);
+
+    assert_eq!(director.neurons().unwrap().len(), 5);
+    assert!(matches!(director.neuron_info(b[2] + 1), Err(Error::UnknownNeuron(_))));
+}
+
+#[test]
+fn links_are_listed_as_built() {
+    let mut sim = Simulation::new(false, None).unwrap();
+    let director = sim.register_director(Director::new(10, 0).unwrap()).unwrap();
+    let (a, b) = network(director);
+
+    assert_eq!(director.outgoing_links(a[0]).unwrap(), [link(a[0], b[0], 0.5), link(a[0], b[1], 0.25)]);
+    assert_eq!(director.outgoing_links(b[1]).unwrap(), []);
+    assert_eq!(director.incoming_links(b[1]).unwrap(), [link(a[0], b[1], 0.25), link(a[1], b[1], -1.)]);
+    assert_eq!(director.incoming_links(b[2]).unwrap(), [link(b[0], b[2], 0.1), link(b[2], b[2], 0.3)]);
+    assert!(matches!(director.outgoing_links(b[2] + 1), Err(Error::UnknownNeuron(_))));
+    assert!(matches!(director.incoming_links(b[2] + 1), Err(Error::UnknownNeuron(_))));
+
+    let mut links: Vec<Link> = director.links().collect();
+    links.sort_by_key(|link| (link.source, link.destination));
+    assert_eq!(links.len(), 5);
+    assert_eq!(director.synapse_count(), 5);
+    assert_eq!(sim.synapse_count(), 5);
+}
+
+#[test]
+fn degree_histograms_count_neurons_per_degree() {
+    let mut director = Director::new(10, 0).unwrap();
+    network(&mut director);
+    /* out degrees 2, 1, 1, 0, 1 and in degrees 0, 0, 1, 2, 2 */
+    assert_eq!(director.out_degree_histogram(), [1, 3, 1]);
+    assert_eq!(director.in_degree_histogram(), [2, 1, 2]);
+}
+
+#[test]
+fn set_weight_changes_a_running_network() {
+    let mut sim = Simulation::new(false, None).unwrap();
+    let director = sim.register_director(Director::new(10, 0).unwrap()).unwrap();
+    let (a, b) = network(director);
+    director.record_potentials(&b[0..1]).unwrap();
+    sim.step().unwrap();
+
+    let director = sim.director_mut(0).unwrap();
+    director.set_weight(a[0], b[0], 0.7).unwrap();
+    assert_eq!(director.weight(a[0], b[0]), Some(0.7));
+    assert_eq!(director.outgoing_links(a[0]).unwrap()[0], link(a[0], b[0], 0.7));
+    assert!(matches!(director.set_weight(a[1], b[0], 1.), Err(Error::UnknownLink { .. })));
+    assert_eq!(director.weight(a[1], b[0]), None);
+
+    director.schedule_spike(a[0], 2).unwrap();
+    sim.run_for(2).unwrap();
+    assert_eq!(sim.directors()[0].recorded_potentials()[&b[0]], [(0, 0.), (1, 0.), (2, 0.7)]);
+}
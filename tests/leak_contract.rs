@@ -0,0 +1,162 @@
+//! Checks recorded LIF potentials against the closed-form solution of the integration contract
+//! documented on `Leaky`: leak first, then input, then threshold and reset. Below threshold
+//! `v(t) = sum over s <= t of beta^(t - s) * I(s)`, counted from the last reset.
+
+mod common;
+
+use std::collections::BTreeMap;
+
+use rust_nn_framewrk::neural_sim::neuron::lif_neuron::LifParams;
+use rust_nn_framewrk::neural_sim::random::Rng;
+use rust_nn_framewrk::neural_sim::{BatchLinkingRule, ControllingUnit, Director, NeuronUniqueId, Simulation, VecOrValueFloat};
+
+use common::{BACKENDS, Backend, population};
+
+const STEPS: u32 = 60;
+const BETAS: [f32; 4] = [0.5, 0.8, 0.95, 1.];
+const TOLERANCE: f64 = 1e-5;
+/// Reference potentials closer than this to the threshold are redrawn, so rounding cannot
+/// decide whether a neuron fires.
+const MARGIN: f64 = 1e-3;
+
+/// How the scheduled input reaches the neuron under test.
+#[derive(Debug, Clone, Copy)]
+enum Delivery {
+    Pulse,
+    /// One driver neuron per input, spiking at the input steps through a link of the input's weight.
+    Synapse,
+}
+
+/// Input of one neuron: step -> summed amplitude.
+type Schedule = BTreeMap<u32, f32>;
+
+/// Closed-form potential and spikes for every step in `0..STEPS`.
+fn closed_form(params: LifParams, schedule: &Schedule) -> (Vec<f64>, Vec<u32>) {
+    let beta = params.beta as f64;
+    let mut last_reset = None;
+    let mut potentials = Vec::new();
+    let mut spikes = Vec::new();
+    for time_step in 0..STEPS {
+        let potential: f64 = schedule
+            .range(last_reset.map_or(0, |reset| reset + 1)..=time_step)
+            .map(|(input_step, amplitude)| beta.powi((time_step - input_step) as i32) * *amplitude as f64)
+            .sum();
+        if potential >= params.threshold as f64 {
+            spikes.push(time_step);
+            potentials.push(0.);
+            last_reset = Some(time_step);
+        } else {
+            potentials.push(potential);
+        }
+    }
+    (potentials, spikes)
+}
+
+/// Whether the reference stays clear of the threshold at every step.
+fn unambiguous(params: LifParams, schedule: &Schedule) -> bool {
+    let beta = params.beta as f64;
+    let mut potential = 0.;
+    (0..STEPS).all(|time_step| {
+        potential = potential * beta + schedule.get(&time_step).copied().unwrap_or(0.) as f64;
+        let clear = (potential - params.threshold as f64).abs() > MARGIN;
+        if potential >= params.threshold as f64 {
+            potential = 0.;
+        }
+        clear
+    })
+}
+
+/// Sparse input with gaps of pure decay; amplitudes of both signs.
+fn random_schedule(rng: &mut Rng, params: LifParams, max_amplitude: f32) -> Schedule {
+    loop {
+        let mut schedule = Schedule::new();
+        let mut time_step = rng.uniform(0., 4.) as u32;
+        while time_step < STEPS - 5 {
+            schedule.insert(time_step, rng.uniform(-0.3 * max_amplitude, max_amplitude));
+            time_step += 1 + rng.uniform(0., 8.) as u32;
+        }
+        if unambiguous(params, &schedule) {
+            return schedule;
+        }
+    }
+}
+
+fn check(backend: Backend, delivery: Delivery, threshold: f32, max_amplitude: f32, seed: u64) {
+    let mut rng = Rng::seed_from_u64(seed);
+    let mut sim = Simulation::new(false, None).unwrap();
+    let director = sim.register_director(Director::new(STEPS, 0).unwrap()).unwrap();
+
+    let mut expected: Vec<(NeuronUniqueId, LifParams, Schedule)> = Vec::new();
+    for (index, beta) in BETAS.iter().enumerate() {
+        let params = LifParams::new(*beta).with_threshold(threshold);
+        let neurons = population(director, backend, &format!("beta_{index}"), 3, params).unwrap();
+        for id in neurons.iter() {
+            let schedule = random_schedule(&mut rng, params, max_amplitude);
+            match delivery {
+                Delivery::Pulse => {
+                    for (time_step, amplitude) in &schedule {
+                        director.schedule_pulse(&[*id], *time_step..time_step + 1, *amplitude).unwrap();
+                    }
+                }
+                Delivery::Synapse => {
+                    let drivers = population(director, backend, &format!("drivers_{id}"), schedule.len(), LifParams::new(0.)).unwrap();
+                    let weights = schedule.values().map(|amplitude| vec![*amplitude]).collect();
+                    for (driver, time_step) in drivers.iter().zip(schedule.keys()) {
+                        director.schedule_spikes(&[*driver], &[*time_step]).unwrap();
+                    }
+                    director
+                        .create_links_by_rule(&drivers, &[*id], VecOrValueFloat::Vec(weights), BatchLinkingRule::FullyConnected)
+                        .unwrap();
+                }
+            }
+            expected.push((*id, params, schedule));
+        }
+    }
+    let ids: Vec<NeuronUniqueId> = expected.iter().map(|(id, ..)| *id).collect();
+    director.record_potentials(&ids).unwrap();
+    director.record_spikes(&ids).unwrap();
+    sim.start().unwrap();
+
+    let director = &sim.directors()[0];
+    for (id, params, schedule) in &expected {
+        let (potentials, spikes) = closed_form(*params, schedule);
+        let recorded = &director.recorded_potentials()[id];
+        assert_eq!(recorded.len(), potentials.len(), "{backend:?}/{delivery:?}: neuron {id} recorded a different number of steps");
+        for ((time_step, found), expected) in recorded.iter().zip(&potentials) {
+            assert!(
+                (*found as f64 - expected).abs() <= TOLERANCE * expected.abs().max(1.),
+                "{backend:?}/{delivery:?} seed {seed}: neuron {id} (beta {}) at step {time_step} has potential {found}, \
+                 closed form gives {expected}; input {schedule:?}",
+                params.beta
+            );
+        }
+        let fired: Vec<u32> = director.recorded_spikes().iter().filter(|(spiking, _)| spiking == id).map(|(_, time_step)| *time_step).collect();
+        assert_eq!(fired, spikes, "{backend:?}/{delivery:?} seed {seed}: spikes of neuron {id} differ from the closed form");
+    }
+}
+
+#[test]
+fn subthreshold_pulses_follow_exponential_decay() {
+    for backend in BACKENDS {
+        for seed in 0..3 {
+            check(backend, Delivery::Pulse, f32::MAX, 1., seed);
+        }
+    }
+}
+
+#[test]
+fn subthreshold_synaptic_input_follows_exponential_decay() {
+    for backend in BACKENDS {
+        check(backend, Delivery::Synapse, f32::MAX, 1., 10);
+    }
+}
+
+#[test]
+fn reset_restarts_the_closed_form() {
+    for backend in BACKENDS {
+        for seed in 20..23 {
+            check(backend, Delivery::Pulse, 1., 0.7, seed);
+        }
+        check(backend, Delivery::Synapse, 1., 0.7, 30);
+    }
+}
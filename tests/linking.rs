@@ -0,0 +1,280 @@
+//! Link creation: policies, linking rules and weight sources.
+
+use rust_nn_framewrk::neural_sim::error::{Error, LinkCreateError, WeightError};
+use rust_nn_framewrk::neural_sim::introspection::Link;
+use rust_nn_framewrk::neural_sim::neuron::lif_neuron::{LifNeuron, LifParams};
+use rust_nn_framewrk::neural_sim::population::{Population, Shape};
+use rust_nn_framewrk::neural_sim::{
+    BatchLinkingRule, ControllingUnit, Director, LinkPolicy, MultapsePolicy, SelfConnectionPolicy, VecOrValueFloat,
+    WeightDistribution,
+};
+
+fn neurons(size: usize, policy: LinkPolicy) -> (Director, Population) {
+    let mut director = Director::new(10, 0).unwrap();
+    director.set_link_policy(policy);
+    let neurons = director.add_population::<LifNeuron>("n", Shape::D1(size), LifParams::new(0.5)).unwrap();
+    (director, neurons)
+}
+
+fn self_policy(self_connections: SelfConnectionPolicy) -> LinkPolicy {
+    LinkPolicy { self_connections, ..LinkPolicy::default() }
+}
+
+fn multapse_policy(multapses: MultapsePolicy) -> LinkPolicy {
+    LinkPolicy { multapses, ..LinkPolicy::default() }
+}
+
+fn link(source: u32, destination: u32, weight: f32) -> Link {
+    Link { source, destination, weight }
+}
+
+#[test]
+fn self_connections_are_allowed_by_default() {
+    let (mut director, n) = neurons(1, LinkPolicy::default());
+    assert_eq!(director.link_policy().self_connections, SelfConnectionPolicy::Allow);
+    director.create_link(n[0], n[0], 0.5).unwrap();
+    assert_eq!(director.outgoing_links(n[0]).unwrap(), [link(n[0], n[0], 0.5)]);
+}
+
+#[test]
+fn skipped_self_connections_are_dropped_silently() {
+    let (mut director, n) = neurons(2, self_policy(SelfConnectionPolicy::Skip));
+    director.create_link(n[0], n[0], 0.5).unwrap();
+    director.create_link(n[0], n[1], 0.5).unwrap();
+    assert_eq!(director.outgoing_links(n[0]).unwrap(), [link(n[0], n[1], 0.5)]);
+}
+
+#[test]
+fn rejected_self_connections_are_an_error() {
+    let (mut director, n) = neurons(2, self_policy(SelfConnectionPolicy::Reject));
+    let error = director.create_link(n[1], n[1], 0.5).unwrap_err();
+    assert!(matches!(error, Error::LinkCreate(LinkCreateError::SelfConnection(id)) if id == n[1]));
+    assert_eq!(director.synapse_count(), 0);
+}
+
+#[test]
+fn multapses_are_allowed_by_default() {
+    let (mut director, n) = neurons(2, LinkPolicy::default());
+    assert_eq!(director.link_policy().multapses, MultapsePolicy::Allow);
+    director.create_link(n[0], n[1], 0.5).unwrap();
+    director.create_link(n[0], n[1], 0.25).unwrap();
+    assert_eq!(director.outgoing_links(n[0]).unwrap(), [link(n[0], n[1], 0.5), link(n[0], n[1], 0.25)]);
+}
+
+#[test]
+fn merged_multapses_add_their_weights() {
+    let (mut director, n) = neurons(2, multapse_policy(MultapsePolicy::Merge));
+    director.create_link(n[0], n[1], 0.5).unwrap();
+    director.create_link(n[0], n[1], 0.25).unwrap();
+    director.create_link(n[1], n[0], 0.25).unwrap();
+    assert_eq!(director.outgoing_links(n[0]).unwrap(), [link(n[0], n[1], 0.75)]);
+    assert_eq!(director.synapse_count(), 2);
+}
+
+#[test]
+fn rejected_multapses_are_an_error() {
+    let (mut director, n) = neurons(2, multapse_policy(MultapsePolicy::Reject));
+    director.create_link(n[0], n[1], 0.5).unwrap();
+    let error = director.create_link(n[0], n[1], 0.25).unwrap_err();
+    assert!(matches!(
+        error,
+        Error::LinkCreate(LinkCreateError::Multapse { source, destination }) if source == n[0] && destination == n[1]
+    ));
+    assert_eq!(director.outgoing_links(n[0]).unwrap(), [link(n[0], n[1], 0.5)]);
+}
+
+#[test]
+fn policies_apply_to_rules_too() {
+    let policy = LinkPolicy { self_connections: SelfConnectionPolicy::Skip, multapses: MultapsePolicy::Merge };
+    let (mut director, n) = neurons(2, policy);
+    let ids = [n[0], n[1]];
+    for _ in 0..2 {
+        director.create_links_by_rule(&ids, &ids, VecOrValueFloat::Val(0.5), BatchLinkingRule::FullyConnected).unwrap();
+    }
+    assert_eq!(director.outgoing_links(n[0]).unwrap(), [link(n[0], n[1], 1.)]);
+    assert_eq!(director.outgoing_links(n[1]).unwrap(), [link(n[1], n[0], 1.)]);
+}
+
+/// Links `n[0..2]` onto `n[2..5]` and returns the created links sorted by source and destination.
+fn project(director: &mut Director, n: &Population, weights: VecOrValueFloat, rule: BatchLinkingRule) -> Result<Vec<Link>, Error> {
+    director.create_links_by_rule(&n[0..2], &n[2..5], weights, rule)?;
+    let mut links: Vec<Link> = director.links().collect();
+    links.sort_by_key(|link| (link.source, link.destination));
+    Ok(links)
+}
+
+fn weight_error(director: &mut Director, n: &Population, weights: VecOrValueFloat) -> WeightError {
+    match project(director, n, weights, BatchLinkingRule::FullyConnected) {
+        Err(Error::Weights(error)) => error,
+        other => panic!("expected a weight error, got {other:?}"),
+    }
+}
+
+#[test]
+fn weight_matrices_must_match_the_projection() {
+    let (mut director, n) = neurons(5, LinkPolicy::default());
+    let error = weight_error(&mut director, &n, VecOrValueFloat::Vec(vec![vec![1.; 3]]));
+    assert_eq!(error, WeightError::RowCount { expected: 2, found: 1 });
+    let error = weight_error(&mut director, &n, VecOrValueFloat::Vec(vec![vec![1.; 3], vec![1.; 2]]));
+    assert_eq!(error, WeightError::ColumnCount { row: 1, expected: 3, found: 2 });
+    let error = weight_error(&mut director, &n, VecOrValueFloat::Sparse(vec![(0, 0, 1.), (2, 1, 1.)]));
+    assert_eq!(error, WeightError::SparseIndex { row: 2, column: 1, rows: 2, columns: 3 });
+    let error = weight_error(&mut director, &n, VecOrValueFloat::Sparse(vec![(1, 3, 1.)]));
+    assert_eq!(error, WeightError::SparseIndex { row: 1, column: 3, rows: 2, columns: 3 });
+    assert_eq!(director.synapse_count(), 0);
+}
+
+#[test]
+fn distributions_must_have_valid_parameters() {
+    let (mut director, n) = neurons(5, LinkPolicy::default());
+    let invalid = [
+        WeightDistribution::Uniform { low: 1., high: 0. },
+        WeightDistribution::Uniform { low: f32::NAN, high: 1. },
+        WeightDistribution::Uniform { low: 0., high: f32::NAN },
+        WeightDistribution::Uniform { low: 0., high: f32::INFINITY },
+        WeightDistribution::Normal { mean: 0., std_dev: -1. },
+        WeightDistribution::Normal { mean: f32::NAN, std_dev: 1. },
+        WeightDistribution::LogNormal { mu: 0., sigma: f32::NAN },
+    ];
+    for distribution in invalid {
+        let error = weight_error(&mut director, &n, VecOrValueFloat::Distribution(distribution));
+        assert!(matches!(error, WeightError::InvalidDistribution(_)), "{distribution:?}");
+        let error = weight_error(&mut director, &n, VecOrValueFloat::Random { distribution, seed: 1 });
+        assert!(matches!(error, WeightError::InvalidDistribution(_)), "{distribution:?}");
+    }
+    assert_eq!(director.synapse_count(), 0);
+}
+
+#[test]
+fn weight_functions_get_positions_in_the_slices() {
+    let (mut director, n) = neurons(5, LinkPolicy::default());
+    let weights = VecOrValueFloat::Fn(Box::new(|i, j| (10 * i + j) as f32));
+    let links = project(&mut director, &n, weights, BatchLinkingRule::FullyConnected).unwrap();
+    let expected: Vec<Link> = (0..2)
+        .flat_map(|i| (0..3).map(move |j| (i, j)))
+        .map(|(i, j)| link(n[i], n[2 + j], (10 * i + j) as f32))
+        .collect();
+    assert_eq!(links, expected);
+}
+
+#[test]
+fn sparse_weights_link_only_listed_pairs_the_rule_selects() {
+    let (mut director, n) = neurons(5, LinkPolicy::default());
+    let weights = VecOrValueFloat::Sparse(vec![(0, 0, 0.5), (0, 2, 0.25), (1, 1, -1.)]);
+    let links = project(&mut director, &n, weights, BatchLinkingRule::FullyConnected).unwrap();
+    assert_eq!(links, [link(n[0], n[2], 0.5), link(n[0], n[4], 0.25), link(n[1], n[3], -1.)]);
+
+    let (mut director, n) = neurons(5, LinkPolicy::default());
+    let weights = VecOrValueFloat::Sparse(vec![(0, 0, 0.5), (0, 2, 0.25), (1, 1, -1.)]);
+    let links = project(&mut director, &n, weights, BatchLinkingRule::UserDefined(|i, j| i == j)).unwrap();
+    assert_eq!(links, [link(n[0], n[2], 0.5), link(n[1], n[3], -1.)]);
+}
+
+#[test]
+fn distribution_weights_follow_the_director_seed() {
+    let weights = |seed: u64| {
+        let (mut director, n) = neurons(5, LinkPolicy::default());
+        director.set_seed(seed);
+        let distribution = WeightDistribution::Uniform { low: 0.2, high: 0.4 };
+        let links = project(&mut director, &n, VecOrValueFloat::Distribution(distribution), BatchLinkingRule::FullyConnected).unwrap();
+        links.iter().map(|link| link.weight).collect::<Vec<f32>>()
+    };
+    let first = weights(7);
+    assert_eq!(first.len(), 6);
+    assert!(first.iter().all(|weight| (0.2..0.4).contains(weight)), "{first:?}");
+    assert!(first.windows(2).any(|pair| pair[0] != pair[1]));
+    assert_eq!(weights(7), first);
+    assert_ne!(weights(8), first);
+}
+
+/// Positions `(i, j)` a rule links from `n[0..3]` onto `n[3..6]`, sorted.
+fn rule_pairs(director: &mut Director, n: &Population, rule: BatchLinkingRule) -> Result<Vec<(usize, usize)>, Error> {
+    director.create_links_by_rule(&n[0..3], &n[3..6], VecOrValueFloat::Val(1.), rule)?;
+    let mut pairs: Vec<(usize, usize)> = director
+        .links()
+        .map(|link| ((link.source - n[0]) as usize, (link.destination - n[3]) as usize))
+        .collect();
+    pairs.sort();
+    Ok(pairs)
+}
+
+fn pairs_of(rule: BatchLinkingRule) -> Vec<(usize, usize)> {
+    let (mut director, n) = neurons(6, LinkPolicy::default());
+    rule_pairs(&mut director, &n, rule).unwrap()
+}
+
+fn rule_error(sources: usize, destinations: usize, rule: BatchLinkingRule) -> LinkCreateError {
+    let (mut director, n) = neurons(sources + destinations, LinkPolicy::default());
+    let result = director.create_links_by_rule(&n[..sources], &n[sources..], VecOrValueFloat::Val(1.), rule);
+    assert_eq!(director.synapse_count(), 0);
+    match result {
+        Err(Error::LinkCreate(error)) => error,
+        other => panic!("expected a link error, got {other:?}"),
+    }
+}
+
+#[test]
+fn none_links_nothing() {
+    assert_eq!(pairs_of(BatchLinkingRule::None), []);
+}
+
+#[test]
+fn fully_connected_links_every_pair() {
+    let all: Vec<(usize, usize)> = (0..3).flat_map(|i| (0..3).map(move |j| (i, j))).collect();
+    assert_eq!(pairs_of(BatchLinkingRule::FullyConnected), all);
+}
+
+#[test]
+fn one_to_one_links_matching_positions() {
+    assert_eq!(pairs_of(BatchLinkingRule::OneToOne), [(0, 0), (1, 1), (2, 2)]);
+    assert_eq!(
+        rule_error(2, 3, BatchLinkingRule::OneToOne),
+        LinkCreateError::LengthMismatch { sources: 2, destinations: 3 }
+    );
+}
+
+#[test]
+fn sparse_links_listed_pairs() {
+    assert_eq!(pairs_of(BatchLinkingRule::Sparse(vec![(2, 0), (0, 1), (1, 2)])), [(0, 1), (1, 2), (2, 0)]);
+    assert_eq!(rule_error(3, 3, BatchLinkingRule::Sparse(vec![(0, 0), (0, 3)])), LinkCreateError::RuleIndex { row: 0, column: 3 });
+    assert_eq!(rule_error(3, 3, BatchLinkingRule::Sparse(vec![(3, 0)])), LinkCreateError::RuleIndex { row: 3, column: 0 });
+}
+
+#[test]
+fn predicates_select_pairs() {
+    assert_eq!(pairs_of(BatchLinkingRule::UserDefined(|i, j| i < j)), [(0, 1), (0, 2), (1, 2)]);
+    let mut calls = 0;
+    let every_other = BatchLinkingRule::Predicate(Box::new(move |_, _| {
+        calls += 1;
+        calls % 2 == 1
+    }));
+    /* the predicate is asked row by row */
+    assert_eq!(pairs_of(every_other), [(0, 0), (0, 2), (1, 1), (2, 0), (2, 2)]);
+}
+
+#[test]
+fn neighborhood_links_positions_within_the_radius() {
+    assert_eq!(pairs_of(BatchLinkingRule::neighborhood(0)), [(0, 0), (1, 1), (2, 2)]);
+    assert_eq!(pairs_of(BatchLinkingRule::neighborhood(1)), [(0, 0), (0, 1), (1, 0), (1, 1), (1, 2), (2, 1), (2, 2)]);
+}
+
+#[test]
+fn probability_links_pairs_drawn_from_the_director_seed() {
+    assert_eq!(pairs_of(BatchLinkingRule::Probability(0.)), []);
+    assert_eq!(pairs_of(BatchLinkingRule::Probability(1.)), pairs_of(BatchLinkingRule::FullyConnected));
+
+    let drawn = |seed: u64| {
+        let (mut director, n) = neurons(6, LinkPolicy::default());
+        director.set_seed(seed);
+        rule_pairs(&mut director, &n, BatchLinkingRule::Probability(0.5)).unwrap()
+    };
+    let first = drawn(3);
+    assert!(!first.is_empty() && first.len() < 9, "{first:?}");
+    assert_eq!(drawn(3), first);
+    assert_ne!((0..8).map(drawn).collect::<Vec<_>>(), vec![first; 8]);
+
+    for p in [-0.1, 1.5, f32::NAN] {
+        let error = rule_error(3, 3, BatchLinkingRule::Probability(p));
+        assert!(matches!(error, LinkCreateError::Probability(found) if found.to_bits() == p.to_bits()), "{error:?}");
+    }
+}
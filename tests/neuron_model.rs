@@ -0,0 +1,88 @@
+//! Populations of `neuron_model!` neurons: the Izhikevich model linked, driven and traced.
+
+use std::path::Path;
+
+use rust_nn_framewrk::neural_sim::current::CurrentSource;
+use rust_nn_framewrk::neural_sim::neuron::izhikevich::{IzhikevichNeuron, IzhikevichParams};
+use rust_nn_framewrk::neural_sim::population::Shape;
+use rust_nn_framewrk::neural_sim::trace::Trace;
+use rust_nn_framewrk::neural_sim::{ControllingUnit, Director, NeuronUniqueId, Simulation};
+
+const STEPS: u32 = 200;
+const DRIVEN_SPIKES: [u32; 5] = [4, 29, 75, 121, 167];
+
+/// A regular spiking cell driven by a constant current, exciting a second one at rest, and a
+/// third cell at rest whose potential a bias current raises every step.
+fn run(trace: Option<&Path>) -> Vec<(NeuronUniqueId, u32)> {
+    let mut sim = Simulation::new(trace.is_some(), trace.and_then(Path::to_str)).unwrap();
+    let director = sim.register_director(Director::new(STEPS, 0).unwrap()).unwrap();
+    let driven = director
+        .add_population::<IzhikevichNeuron>("driven", Shape::D1(1), IzhikevichParams { current: 10., ..Default::default() })
+        .unwrap();
+    let follower = director.add_population::<IzhikevichNeuron>("follower", Shape::D1(2), IzhikevichParams::default()).unwrap();
+    director.create_link(driven[0], follower[0], 40.).unwrap();
+    director.inject_current(&follower[1..2], CurrentSource::Bias(5.)).unwrap();
+    let ids = director.neuron_ids().to_vec();
+    director.record_spikes(&ids).unwrap();
+    sim.start().unwrap();
+    let mut spikes = sim.directors()[0].recorded_spikes().to_vec();
+    spikes.sort_by_key(|(id, time_step)| (*time_step, *id));
+    spikes
+}
+
+fn spikes_of(spikes: &[(NeuronUniqueId, u32)], id: NeuronUniqueId) -> Vec<u32> {
+    spikes.iter().filter(|(spiking, _)| *spiking == id).map(|(_, time_step)| *time_step).collect()
+}
+
+#[test]
+fn linked_izhikevich_neurons_spike() {
+    let spikes = run(None);
+    assert_eq!(spikes_of(&spikes, 0), DRIVEN_SPIKES);
+    /* every spike of the driven cell pushes the follower over its peak within two steps */
+    assert_eq!(spikes_of(&spikes, 1), [5, 31, 77, 123, 169]);
+    assert_eq!(spikes_of(&spikes, 2), [7, 99, 195]);
+}
+
+/// Potential of the driven cell at the end of every step, integrated like the model: two
+/// forward Euler substeps per step, then the threshold check and reset.
+fn reference_potentials() -> Vec<f32> {
+    let params = IzhikevichParams { current: 10., ..Default::default() };
+    let (mut v, mut u) = (params.c, params.b * params.c);
+    let mut potentials = vec![v];
+    for _ in 1..STEPS {
+        for _ in 0..2 {
+            let dv = 0.04 * v * v + 5. * v + 140. - u + params.current;
+            let du = params.a * (params.b * v - u);
+            (v, u) = (v + 0.5 * dv, u + 0.5 * du);
+        }
+        if v >= params.v_peak {
+            (v, u) = (params.c, u + params.d);
+        }
+        potentials.push(v);
+    }
+    potentials
+}
+
+#[test]
+fn traced_potentials_follow_the_equations() {
+    let path = std::env::temp_dir().join(format!("neuron_model_{}.vcd", std::process::id()));
+    let spikes = run(Some(&path));
+    let trace = Trace::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let traced_spikes: Vec<(NeuronUniqueId, u32)> = trace.spikes("0").into_iter().map(|(id, time)| (id, time as u32)).collect();
+    assert_eq!(traced_spikes, spikes);
+
+    let driven = trace.neuron("0", 0).unwrap();
+    assert_eq!(driven.population.as_deref(), Some("driven"));
+    for (time_step, expected) in reference_potentials().into_iter().enumerate() {
+        let found = driven.potential_at(time_step as u64).unwrap();
+        assert!((found - expected as f64).abs() < 1e-3, "potential at step {time_step} is {found}, expected {expected}");
+    }
+    /* traced potentials are taken after the reset of a spike */
+    for time_step in DRIVEN_SPIKES {
+        assert_eq!(driven.potential_at(time_step as u64), Some(-65.));
+    }
+    let follower = trace.neuron("0", 1).unwrap();
+    assert!(follower.potential_at(4).unwrap() > follower.potential_at(3).unwrap() + 30.);
+}
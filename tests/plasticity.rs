@@ -0,0 +1,128 @@
+//! Learning and homeostatic rules run by directors at the end of every step.
+
+mod common;
+
+use rust_nn_framewrk::neural_sim::homeostasis::{AdaptiveThreshold, AdaptiveThresholdParams, SynapticScaling, SynapticScalingParams};
+use rust_nn_framewrk::neural_sim::neuron::lif_neuron::LifParams;
+use rust_nn_framewrk::neural_sim::plasticity::{RewardStdp, RewardStdpParams};
+use rust_nn_framewrk::neural_sim::population::Shape;
+use rust_nn_framewrk::neural_sim::{ControllingUnit, Director, NeuronUniqueId, Simulation};
+
+use common::{BACKENDS, population};
+
+/// Runs a causal pairing on an excitatory and an inhibitory synapse onto the same neuron,
+/// then releases `reward` and returns both weights a while later.
+fn rewarded_weights(reward: f32) -> (f32, f32) {
+    let mut sim = Simulation::new(false, None).unwrap();
+    let director = sim.register_director(Director::new(100, 0).unwrap()).unwrap();
+    let sources = director.add_vectorized_population("sources", Shape::D1(2), LifParams::new(0.9)).unwrap();
+    let target = director.add_vectorized_population("target", Shape::D1(1), LifParams::new(0.9)).unwrap();
+    director.create_link(sources[0], target[0], 0.5).unwrap();
+    director.create_link(sources[1], target[0], -0.5).unwrap();
+    let params = RewardStdpParams { learning_rate: 0.02, ..Default::default() };
+    director.add_plasticity(RewardStdp::new(director, &sources, &target, params).unwrap());
+    director.schedule_spikes(&sources, &[1]).unwrap();
+    director.schedule_spikes(&target, &[2]).unwrap();
+
+    sim.run_for(4).unwrap();
+    sim.release_neuromodulator(RewardStdp::DEFAULT_CHANNEL, reward);
+    sim.run_for(20).unwrap();
+    let director = &sim.directors()[0];
+    (director.weight(sources[0], target[0]).unwrap(), director.weight(sources[1], target[0]).unwrap())
+}
+
+#[test]
+fn reward_moves_weights_of_both_signs() {
+    let (excitatory, inhibitory) = rewarded_weights(1.);
+    assert!(excitatory > 0.5 && excitatory <= 1., "excitatory weight {excitatory}");
+    assert!(inhibitory > -0.5 && inhibitory < 0., "inhibitory weight {inhibitory}");
+}
+
+#[test]
+fn weights_are_clamped_by_their_sign() {
+    let (excitatory, inhibitory) = rewarded_weights(-100.);
+    assert_eq!(excitatory, 0.);
+    assert_eq!(inhibitory, -1.);
+
+    let (excitatory, inhibitory) = rewarded_weights(100.);
+    assert_eq!(excitatory, 1.);
+    assert_eq!(inhibitory, 0.);
+}
+
+#[test]
+fn synaptic_scaling_leaves_inhibitory_weights_alone() {
+    let mut sim = Simulation::new(false, None).unwrap();
+    let director = sim.register_director(Director::new(100, 0).unwrap()).unwrap();
+    let sources = director.add_vectorized_population("sources", Shape::D1(2), LifParams::new(0.9)).unwrap();
+    let target = director.add_vectorized_population("target", Shape::D1(1), LifParams::new(0.9)).unwrap();
+    director.create_link(sources[0], target[0], 0.5).unwrap();
+    director.create_link(sources[1], target[0], -0.5).unwrap();
+    let params = SynapticScalingParams { interval: 10, ..Default::default() };
+    director.add_plasticity(SynapticScaling::new(director, &target, params).unwrap());
+
+    /* the target never fires, so both scalings grow its excitatory input by 10% */
+    sim.run_for(25).unwrap();
+    let director = &sim.directors()[0];
+    assert!((director.weight(sources[0], target[0]).unwrap() - 0.5 * 1.1 * 1.1).abs() < 1e-6);
+    assert_eq!(director.weight(sources[1], target[0]).unwrap(), -0.5);
+}
+
+#[test]
+fn synaptic_scaling_scales_parallel_links_one_by_one() {
+    let mut sim = Simulation::new(false, None).unwrap();
+    let director = sim.register_director(Director::new(100, 0).unwrap()).unwrap();
+    let source = director.add_vectorized_population("source", Shape::D1(1), LifParams::new(0.9)).unwrap();
+    let target = director.add_vectorized_population("target", Shape::D1(1), LifParams::new(0.).with_threshold(100.)).unwrap();
+    for weight in [0.5, 0.2, -0.3] {
+        director.create_link(source[0], target[0], weight).unwrap();
+    }
+    let params = SynapticScalingParams { interval: 10, ..Default::default() };
+    director.add_plasticity(SynapticScaling::new(director, &target, params).unwrap());
+    director.schedule_spike(source[0], 27).unwrap();
+    director.record_potentials(&target).unwrap();
+
+    /* scalings at steps 10 and 20, each growing the excitatory links by 10% */
+    sim.run_for(28).unwrap();
+    let director = &sim.directors()[0];
+    let weights: Vec<f32> = director.incoming_links(target[0]).unwrap().iter().map(|link| link.weight).collect();
+    let expected = [0.5 * 1.21, 0.2 * 1.21, -0.3];
+    assert!(weights.iter().zip(&expected).all(|(weight, expected)| (weight - expected).abs() < 1e-6), "{weights:?}");
+    /* the spike is delivered through the scaled links */
+    let (step, potential) = director.recorded_potentials()[&target[0]][27];
+    assert_eq!(step, 27);
+    assert!((potential - expected.iter().sum::<f32>()).abs() < 1e-6, "{potential}");
+}
+
+fn threshold(sim: &Simulation, id: NeuronUniqueId) -> f32 {
+    let info = sim.directors()[0].neuron_info(id).unwrap();
+    info.parameters.iter().find(|(name, _)| *name == "threshold").unwrap().1
+}
+
+#[test]
+fn adaptive_threshold_rises_on_spikes_and_decays_back() {
+    for backend in BACKENDS {
+        let mut sim = Simulation::new(false, None).unwrap();
+        let director = sim.register_director(Director::new(200, 0).unwrap()).unwrap();
+        let neuron = population(director, backend, "neuron", 1, LifParams::new(0.9)).unwrap();
+        let params = AdaptiveThresholdParams { increment: 0.5, tau: 10., ..Default::default() };
+        director.add_plasticity(AdaptiveThreshold::new(&neuron, params));
+        director.schedule_spikes(&neuron, &[2, 3]).unwrap();
+        let expected = |adaptation: f32| 1. + adaptation;
+        let close = |found: f32, expected: f32| (found - expected).abs() < 1e-5;
+
+        sim.run_for(2).unwrap();
+        assert_eq!(threshold(&sim, neuron[0]), 1., "{backend:?}");
+        sim.run_for(1).unwrap();
+        assert!(close(threshold(&sim, neuron[0]), expected(0.5)), "{backend:?}");
+        /* the second spike adds to what is left of the first */
+        sim.run_for(1).unwrap();
+        let after_both = 0.5 * (-0.1f32).exp() + 0.5;
+        assert!(close(threshold(&sim, neuron[0]), expected(after_both)), "{backend:?}");
+        sim.run_for(10).unwrap();
+        let found = threshold(&sim, neuron[0]);
+        assert!(close(found, expected(after_both * (-1f32).exp())), "{backend:?}: {found}");
+        sim.run_for(150).unwrap();
+        let found = threshold(&sim, neuron[0]);
+        assert!(close(found, 1.), "{backend:?}: {found}");
+    }
+}
@@ -0,0 +1,74 @@
+//! SVG and terminal rendering of recorded activity.
+
+use rust_nn_framewrk::neural_sim::current::CurrentSource;
+use rust_nn_framewrk::neural_sim::neuron::lif_neuron::LifParams;
+use rust_nn_framewrk::neural_sim::plot::Plot;
+use rust_nn_framewrk::neural_sim::population::Shape;
+use rust_nn_framewrk::neural_sim::{Director, Simulation};
+
+const NAME: &str = r#"a<b & "c">"#;
+const ESCAPED: &str = "a&lt;b &amp; &quot;c&quot;&gt;";
+
+/// Runs 20 steps of two neurons, the first one driven to fire every fourth step.
+fn recorded_simulation() -> Simulation {
+    let mut sim = Simulation::new(false, None).unwrap();
+    let director = sim.register_director(Director::new(100, 0).unwrap()).unwrap();
+    let neurons = director.add_vectorized_population(NAME, Shape::D1(2), LifParams::new(1.)).unwrap();
+    director.inject_current(&neurons[0..1], CurrentSource::Bias(0.3)).unwrap();
+    director.record_spikes(&neurons).unwrap();
+    director.record_potentials(&neurons).unwrap();
+    sim.run_for(20).unwrap();
+    sim
+}
+
+#[test]
+fn population_names_are_escaped_in_svg() {
+    let sim = recorded_simulation();
+    let plot = Plot::new(&sim.directors()[0]);
+    for svg in [plot.raster_svg(), plot.rate_histogram_svg(5)] {
+        assert!(svg.contains(ESCAPED), "{svg}");
+        assert!(!svg.contains(NAME), "{svg}");
+        assert!(svg.trim_end().ends_with("</svg>"));
+    }
+}
+
+#[test]
+fn raster_marks_every_visible_spike() {
+    let sim = recorded_simulation();
+    let director = &sim.directors()[0];
+    let spikes = director.recorded_spikes().len();
+    assert_eq!(spikes, 5);
+    let marks = |svg: String| svg.matches("<rect x=").count();
+    /* the background and the frame are rects as well */
+    assert_eq!(marks(Plot::new(director).raster_svg()), spikes + 1);
+    assert_eq!(marks(Plot::new(director).with_window(0..10).raster_svg()), 2 + 1);
+}
+
+#[test]
+fn recorded_potentials_stay_below_the_threshold_line() {
+    let sim = recorded_simulation();
+    let director = &sim.directors()[0];
+    let svg = Plot::new(director).potentials_svg(&[]);
+    assert_eq!(svg.matches("<polyline").count(), 2);
+    assert_eq!(svg.matches("stroke-dasharray").count(), 2);
+
+    /* potentials are recorded after the reset of the spikes they led to */
+    let potentials = &director.recorded_potentials()[&0];
+    assert_eq!(potentials.len(), 20);
+    assert!(potentials.iter().all(|(_, potential)| *potential < 1.));
+    assert!(potentials.iter().any(|(_, potential)| *potential == 0.));
+}
+
+#[test]
+fn terminal_output_is_labeled() {
+    let sim = recorded_simulation();
+    let plot = Plot::new(&sim.directors()[0]).with_color(false);
+    let raster = plot.raster_terminal(20, 2);
+    assert!(raster.starts_with("steps 0..20, neurons 0..2\n"), "{raster}");
+    assert!(raster.lines().skip(1).all(|line| line.ends_with(NAME)), "{raster}");
+    assert!(!raster.contains('\x1b'));
+
+    let potential = plot.potential_terminal(0, 20, 3);
+    assert!(potential.starts_with("neuron 0, steps 0..20"), "{potential}");
+    assert_eq!(potential.lines().count(), 4);
+}
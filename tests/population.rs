@@ -0,0 +1,112 @@
+//! Named populations: coordinates, slices and registration errors.
+
+use rust_nn_framewrk::neural_sim::Director;
+use rust_nn_framewrk::neural_sim::error::{Error, PopulationError};
+use rust_nn_framewrk::neural_sim::neuron::lif_neuron::{LifNeuron, LifParams};
+use rust_nn_framewrk::neural_sim::population::{Population, Shape};
+
+/// A 3x4 grid registered after a single neuron, so its ids do not start at the first id of the director.
+fn grid(director: &mut Director) -> Population {
+    director.add_population::<LifNeuron>("first", Shape::D1(1), LifParams::new(0.5)).unwrap();
+    director.add_population::<LifNeuron>("grid", Shape::D2(3, 4), LifParams::new(0.5)).unwrap()
+}
+
+fn population_error(result: Result<Population, Error>) -> PopulationError {
+    match result {
+        Err(Error::Population(error)) => error,
+        other => panic!("expected a population error, got {other:?}"),
+    }
+}
+
+#[test]
+fn coordinates_are_row_major() {
+    let mut director = Director::new(10, 0).unwrap();
+    let grid = grid(&mut director);
+    let first = grid[0];
+    assert_eq!(grid.at(&[0, 0]), Some(first));
+    assert_eq!(grid.at(&[0, 3]), Some(first + 3));
+    assert_eq!(grid.at(&[2, 1]), Some(first + 9));
+    assert_eq!(grid.at(&[3, 0]), None);
+    assert_eq!(grid.at(&[0, 4]), None);
+    assert_eq!(grid.at(&[1]), None);
+    assert_eq!(grid.at(&[1, 1, 0]), None);
+}
+
+#[test]
+fn id_range_covers_contiguous_populations() {
+    let mut director = Director::new(10, 0).unwrap();
+    let grid = grid(&mut director);
+    let first = director.neuron_ids()[1];
+    assert_eq!(grid.id_range(), Some(first..first + 12));
+    assert_eq!(grid.to_string(), format!("grid (3x4 lif, ids {}..{})", first, first + 12));
+
+    let row = grid.slice(&[1..2, 0..4]).unwrap();
+    assert_eq!(row.id_range(), Some(first + 4..first + 8));
+    let column = grid.slice(&[0..3, 1..2]).unwrap();
+    assert_eq!(column.id_range(), None);
+    assert_eq!(column.to_string(), "grid[0..3,1..2] (3x1 lif, 3 ids)");
+    assert_eq!(grid.slice(&[1..1, 0..4]).unwrap().id_range(), None);
+}
+
+#[test]
+fn slices_select_a_block_of_coordinates() {
+    let mut director = Director::new(10, 0).unwrap();
+    let grid = grid(&mut director);
+    let first = grid[0];
+
+    let block = grid.slice(&[1..3, 1..3]).unwrap();
+    assert_eq!(block.name(), "grid[1..3,1..3]");
+    assert_eq!(block.shape(), Shape::D2(2, 2));
+    assert_eq!(block.kind(), "lif");
+    assert_eq!(block.ids(), [first + 5, first + 6, first + 9, first + 10]);
+    assert_eq!(block.at(&[1, 0]), grid.at(&[2, 1]));
+
+    let empty = grid.slice(&[0..3, 2..2]).unwrap();
+    assert_eq!(empty.shape(), Shape::D2(3, 0));
+    assert!(empty.is_empty());
+    assert_eq!(grid.slice(&[0..3, 0..4]).unwrap().ids(), grid.ids());
+}
+
+#[test]
+fn slices_out_of_range_are_rejected() {
+    let mut director = Director::new(10, 0).unwrap();
+    let grid = grid(&mut director);
+    assert_eq!(population_error(grid.slice(&[0..3, 0..4, 0..1])), PopulationError::Dimensions { expected: 2, found: 3 });
+    assert_eq!(
+        population_error(grid.slice(&[0..4, 0..4])),
+        PopulationError::OutOfRange { dimension: 0, end: 4, size: 3 }
+    );
+    assert_eq!(
+        population_error(grid.slice(&[0..3, 2..5])),
+        PopulationError::OutOfRange { dimension: 1, end: 5, size: 4 }
+    );
+    #[allow(clippy::reversed_empty_ranges)]
+    let inverted = grid.slice(&[2..1, 0..4]);
+    assert_eq!(population_error(inverted), PopulationError::OutOfRange { dimension: 0, end: 1, size: 3 });
+}
+
+#[test]
+fn population_names_are_unique_per_director() {
+    let mut director = Director::new(10, 0).unwrap();
+    grid(&mut director);
+    let booked = director.neuron_ids().len();
+
+    let threaded = director.add_population::<LifNeuron>("grid", Shape::D1(2), LifParams::new(0.5));
+    assert_eq!(population_error(threaded), PopulationError::DuplicateName("grid".to_string()));
+    let vectorized = director.add_vectorized_population("first", Shape::D1(2), LifParams::new(0.5));
+    assert_eq!(population_error(vectorized), PopulationError::DuplicateName("first".to_string()));
+    assert_eq!(director.neuron_ids().len(), booked);
+    assert_eq!(director.populations().len(), 2);
+
+    let mut other = Director::new(10, 1).unwrap();
+    assert!(other.add_population::<LifNeuron>("grid", Shape::D1(2), LifParams::new(0.5)).is_ok());
+}
+
+#[test]
+fn registered_neurons_must_fill_the_shape() {
+    let mut director = Director::new(10, 0).unwrap();
+    let neurons = vec![LifNeuron::new(0.5); 5];
+    let error = population_error(director.register_population("grid", Shape::D2(2, 3), neurons));
+    assert_eq!(error, PopulationError::ShapeMismatch { expected: 6, found: 5 });
+    assert!(director.neuron_ids().is_empty());
+}
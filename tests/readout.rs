@@ -0,0 +1,85 @@
+//! Classifying samples by the activity of an output population.
+
+mod common;
+
+use rust_nn_framewrk::neural_sim::error::Error;
+use rust_nn_framewrk::neural_sim::neuron::ResetMode;
+use rust_nn_framewrk::neural_sim::neuron::lif_neuron::LifParams;
+use rust_nn_framewrk::neural_sim::readout::{Classifier, ReadoutMode, Sample, SampleReset};
+use rust_nn_framewrk::neural_sim::{BatchLinkingRule, ControllingUnit, Director, Simulation, VecOrValueFloat};
+
+use common::{BACKENDS, Backend, population};
+
+/// Every input neuron drives the output neuron of the same class above threshold.
+fn classifier(backend: Backend) -> (Simulation, Classifier) {
+    let mut sim = Simulation::new(false, None).unwrap();
+    let director = sim.register_director(Director::new(100, 0).unwrap()).unwrap();
+    let input = population(director, backend, "input", 3, LifParams::new(0.5)).unwrap();
+    let output = population(director, backend, "output", 3, LifParams::new(0.5)).unwrap();
+    director.create_links_by_rule(&input, &output, VecOrValueFloat::Val(1.2), BatchLinkingRule::OneToOne).unwrap();
+    let classifier = Classifier::new(0, input, output, 6);
+    (sim, classifier)
+}
+
+fn sample(label: usize, input: [&[u32]; 3]) -> Sample {
+    Sample { input: input.iter().map(|times| times.to_vec()).collect(), label }
+}
+
+#[test]
+fn evaluate_counts_predictions_per_label() {
+    let dataset = [
+        sample(0, [&[0, 2, 4], &[], &[]]),
+        sample(1, [&[], &[1, 3], &[]]),
+        sample(2, [&[], &[], &[0]]),
+        sample(0, [&[0], &[], &[]]),
+        /* wrongly labelled: the network sees class 1 */
+        sample(2, [&[5], &[0, 1, 2], &[]]),
+        /* no input, the output stays silent */
+        sample(1, [&[], &[], &[]]),
+    ];
+    for backend in BACKENDS {
+        let (mut sim, classifier) = classifier(backend);
+        assert_eq!(classifier.classes(), 3);
+        let evaluation = classifier.evaluate(&mut sim, &dataset).unwrap();
+        assert_eq!(evaluation.predictions, [Some(0), Some(1), Some(2), Some(0), Some(1), None], "{backend:?}");
+        assert_eq!(evaluation.confusion, [vec![2, 0, 0], vec![0, 1, 0], vec![0, 1, 1]], "{backend:?}");
+        assert_eq!(evaluation.accuracy, 4. / 6., "{backend:?}");
+        /* six samples of six steps each, the reset between them keeps time running */
+        assert_eq!(sim.directors()[0].cur_time(), 36, "{backend:?}");
+    }
+}
+
+#[test]
+fn readout_modes_pick_different_winners() {
+    /* class 0 spikes first, class 1 most often */
+    let input = sample(0, [&[1], &[2, 3, 4], &[]]).input;
+    for backend in BACKENDS {
+        let (mut sim, classifier) = classifier(backend);
+        assert_eq!(classifier.predict(&mut sim, &input).unwrap(), Some(1), "{backend:?}");
+        let classifier = classifier.with_mode(ReadoutMode::FirstSpike);
+        assert_eq!(classifier.predict(&mut sim, &input).unwrap(), Some(0), "{backend:?}");
+    }
+}
+
+#[test]
+fn full_reset_between_samples_rewinds_time() {
+    /* the spike at step 8 lies beyond the sample and is never planned, so nothing leaks into the next one */
+    let late = sample(0, [&[5, 8], &[], &[]]).input;
+    let empty = sample(0, [&[], &[], &[]]).input;
+    for backend in BACKENDS {
+        let (mut sim, classifier) = classifier(backend);
+        let classifier = classifier.with_reset(SampleReset::Reset(ResetMode::Full));
+        assert_eq!(classifier.predict(&mut sim, &late).unwrap(), Some(0), "{backend:?}");
+        assert_eq!(classifier.predict(&mut sim, &empty).unwrap(), None, "{backend:?}");
+        assert_eq!(sim.directors()[0].cur_time(), 6, "{backend:?}");
+    }
+}
+
+#[test]
+fn samples_must_match_the_classifier() {
+    let (mut sim, classifier) = classifier(Backend::Vectorized);
+    let short = vec![vec![0], vec![1]];
+    assert!(matches!(classifier.predict(&mut sim, &short), Err(Error::SampleShape { expected: 3, found: 2 })));
+    let unknown_label = [sample(3, [&[0], &[], &[]])];
+    assert!(matches!(classifier.evaluate(&mut sim, &unknown_label), Err(Error::SampleShape { expected: 3, found: 4 })));
+}
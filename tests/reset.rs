@@ -0,0 +1,107 @@
+//! What every `ResetMode` clears, on both backends.
+
+mod common;
+
+use rust_nn_framewrk::neural_sim::neuron::{CommonlyCreateable, ResetMode};
+use rust_nn_framewrk::neural_sim::neuron::izhikevich::{IzhikevichNeuron, IzhikevichParams};
+use rust_nn_framewrk::neural_sim::neuron::lif_neuron::{LifNeuron, LifParams};
+use rust_nn_framewrk::neural_sim::population::Shape;
+use rust_nn_framewrk::neural_sim::{Director, NeuronUniqueId, Simulation};
+
+use common::{BACKENDS, Backend, population};
+
+/// Recorded `(spikes, potentials)` of one neuron that got a pulse of 0.4 at step 1 and has a
+/// spike planned at step 5 and a pulse of 0.6 at step 6, reset with `mode` after 3 steps and
+/// run for 5 more.
+fn reset_after_three_steps(backend: Backend, mode: ResetMode) -> (Vec<u32>, Vec<(u32, f32)>, u32) {
+    let mut sim = Simulation::new(false, None).unwrap();
+    let director = sim.register_director(Director::new(20, 0).unwrap()).unwrap();
+    let neuron = population(director, backend, "neuron", 1, LifParams::new(0.5)).unwrap();
+    director.schedule_pulse(&neuron, 1..2, 0.4).unwrap();
+    director.schedule_spike(neuron[0], 5).unwrap();
+    director.schedule_pulse(&neuron, 6..7, 0.6).unwrap();
+    director.record_spikes(&neuron).unwrap();
+    director.record_potentials(&neuron).unwrap();
+    sim.run_for(3).unwrap();
+    sim.reset(mode).unwrap();
+    let time_after_reset = sim.directors()[0].cur_time();
+    sim.run_for(5).unwrap();
+
+    let director = &sim.directors()[0];
+    let spikes = director.recorded_spikes().iter().map(|(_, time_step)| *time_step).collect();
+    (spikes, director.recorded_potentials()[&neuron[0]].clone(), time_after_reset)
+}
+
+fn assert_potentials(found: &[(u32, f32)], expected: &[(u32, f32)], context: String) {
+    assert_eq!(found.len(), expected.len(), "{context}: {found:?}");
+    for ((step, found), (expected_step, expected)) in found.iter().zip(expected) {
+        assert_eq!(step, expected_step, "{context}: {found:?}");
+        assert!((found - expected).abs() < 1e-6, "{context}: step {step} got {found}, expected {expected}");
+    }
+}
+
+#[test]
+fn potentials_reset_keeps_planned_spikes_and_stimuli() {
+    for backend in BACKENDS {
+        let (spikes, potentials, time) = reset_after_three_steps(backend, ResetMode::Potentials);
+        assert_eq!(time, 3, "{backend:?}");
+        assert_eq!(spikes, [5], "{backend:?}");
+        let expected = [(0, 0.), (1, 0.4), (2, 0.2), (3, 0.), (4, 0.), (5, 0.), (6, 0.6), (7, 0.3)];
+        assert_potentials(&potentials, &expected, format!("{backend:?}"));
+    }
+}
+
+#[test]
+fn state_reset_drops_planned_spikes_and_stimuli() {
+    for backend in BACKENDS {
+        let (spikes, potentials, time) = reset_after_three_steps(backend, ResetMode::State);
+        assert_eq!(time, 3, "{backend:?}");
+        assert_eq!(spikes, [], "{backend:?}");
+        let expected = [(0, 0.), (1, 0.4), (2, 0.2), (3, 0.), (4, 0.), (5, 0.), (6, 0.), (7, 0.)];
+        assert_potentials(&potentials, &expected, format!("{backend:?}"));
+    }
+}
+
+#[test]
+fn full_reset_rewinds_time_and_recordings() {
+    for backend in BACKENDS {
+        let (spikes, potentials, time) = reset_after_three_steps(backend, ResetMode::Full);
+        assert_eq!(time, 0, "{backend:?}");
+        assert_eq!(spikes, [], "{backend:?}");
+        let expected = [(0, 0.), (1, 0.), (2, 0.), (3, 0.), (4, 0.)];
+        assert_potentials(&potentials, &expected, format!("{backend:?}"));
+    }
+}
+
+/// Spike times of a LIF neuron with an init impulse at step 2 and an Izhikevich neuron with
+/// one at step 1, run for 4 steps after `before` and 4 more after `after`.
+fn init_impulses(before: Option<ResetMode>, after: ResetMode) -> Vec<(NeuronUniqueId, u32)> {
+    let mut sim = Simulation::new(false, None).unwrap();
+    let director = sim.register_director(Director::new(20, 0).unwrap()).unwrap();
+    let mut lif = LifNeuron::new(0.5);
+    lif.plan_init_impulses(vec![2]);
+    let lif = director.register_population("lif", Shape::D1(1), vec![lif]).unwrap();
+    let mut izhikevich = IzhikevichNeuron::create_new(IzhikevichParams::default());
+    izhikevich.plan_init_impulses(vec![1]);
+    let izhikevich = director.register_population("izhikevich", Shape::D1(1), vec![izhikevich]).unwrap();
+    director.record_spikes(&[lif[0], izhikevich[0]]).unwrap();
+    if let Some(mode) = before {
+        sim.reset(mode).unwrap();
+    }
+    sim.run_for(4).unwrap();
+    sim.reset(after).unwrap();
+    sim.run_for(4).unwrap();
+    sim.directors()[0].recorded_spikes().to_vec()
+}
+
+#[test]
+fn init_impulses_are_planned_once_per_full_reset() {
+    let (lif, izhikevich) = (0, 1);
+    /* a reset before the start must not plan them on top of init */
+    for mode in [ResetMode::Potentials, ResetMode::State, ResetMode::Full] {
+        assert_eq!(init_impulses(Some(mode), ResetMode::Potentials), [(izhikevich, 1), (lif, 2)], "{mode:?}");
+    }
+    assert_eq!(init_impulses(None, ResetMode::State), [(izhikevich, 1), (lif, 2)]);
+    assert_eq!(init_impulses(None, ResetMode::Full), [(izhikevich, 1), (lif, 2)]);
+    assert_eq!(init_impulses(Some(ResetMode::Full), ResetMode::Full), [(izhikevich, 1), (lif, 2)]);
+}
@@ -0,0 +1,216 @@
+//! Behaviour of the simulation and director API shared by all backends.
+
+mod common;
+
+use rust_nn_framewrk::neural_sim::current::CurrentSource;
+use rust_nn_framewrk::neural_sim::error::{Error, LinkCreateError};
+use rust_nn_framewrk::neural_sim::introspection::Link;
+use rust_nn_framewrk::neural_sim::neuron::lif_neuron::{LifNeuron, LifParams};
+use rust_nn_framewrk::neural_sim::population::Shape;
+use rust_nn_framewrk::neural_sim::{
+    BatchLinkingRule, ControllingUnit, Director, MAX_DIRECTOR_ID, NeuronUniqueId, Simulation, VecOrValueFloat, WeightDistribution,
+};
+
+use common::{BACKENDS, population};
+
+#[test]
+fn run_until_stops_at_the_step_limit() {
+    let mut sim = Simulation::new(false, None).unwrap();
+    let director = sim.register_director(Director::new(100, 0).unwrap()).unwrap();
+    let neuron = director.add_vectorized_population("neuron", Shape::D1(1), LifParams::new(0.9)).unwrap();
+    director.schedule_spikes(&neuron, &[3]).unwrap();
+    director.record_spikes(&neuron).unwrap();
+
+    let fired = |sim: &Simulation| !sim.directors()[0].recorded_spikes().is_empty();
+    assert_eq!(sim.run_until(10, fired).unwrap(), 4);
+    assert!(matches!(sim.run_until(5, |_| false), Err(Error::StepLimit(5))));
+}
+
+#[test]
+fn ids_of_another_director_are_rejected() {
+    let mut sim = Simulation::new(false, None).unwrap();
+    let other = sim.register_director(Director::new(20, 1).unwrap()).unwrap();
+    let b = other.add_population::<LifNeuron>("b", Shape::D1(2), LifParams::new(0.9)).unwrap();
+    let director = sim.register_director(Director::new(20, 0).unwrap()).unwrap();
+    let a = director.add_population::<LifNeuron>("a", Shape::D1(2), LifParams::new(0.9)).unwrap();
+
+    assert!(matches!(
+        director.create_link(a[0], b[1], 1.),
+        Err(Error::LinkCreate(LinkCreateError::UnknownDestination(id))) if id == b[1]
+    ));
+    assert!(matches!(
+        director.create_link(b[0], a[1], 1.),
+        Err(Error::LinkCreate(LinkCreateError::UnknownSource(id))) if id == b[0]
+    ));
+    assert!(director.create_links_by_rule(&a, &b, VecOrValueFloat::Val(1.), BatchLinkingRule::OneToOne).is_err());
+    assert!(matches!(director.schedule_spikes(&b[0..1], &[1]), Err(Error::UnknownNeuron(_))));
+    assert!(matches!(director.record_spikes(&b), Err(Error::UnknownNeuron(_))));
+    assert_eq!(director.synapse_count(), 0);
+
+    /* both directors keep working with their own, disjoint ids */
+    director.create_link(a[0], a[1], 1.).unwrap();
+    director.schedule_spikes(&a[0..1], &[1]).unwrap();
+    director.record_spikes(&a).unwrap();
+    let other = sim.director_mut(0).unwrap();
+    other.create_link(b[0], b[1], 1.).unwrap();
+    other.schedule_spikes(&b[0..1], &[2]).unwrap();
+    other.record_spikes(&b).unwrap();
+    sim.start().unwrap();
+    assert_eq!(sim.directors()[1].recorded_spikes(), &[(a[0], 1), (a[1], 1)]);
+    assert_eq!(sim.directors()[0].recorded_spikes(), &[(b[0], 2), (b[1], 2)]);
+}
+
+#[test]
+fn director_ids_are_unique_and_bounded() {
+    assert!(Director::new(10, MAX_DIRECTOR_ID).is_none());
+    let mut sim = Simulation::new(false, None).unwrap();
+    assert!(sim.register_director(Director::new(10, 3).unwrap()).is_ok());
+    assert!(matches!(sim.register_director(Director::new(10, 3).unwrap()), Err(Error::DuplicateDirector(3))));
+}
+
+#[test]
+fn directors_cannot_join_a_running_simulation() {
+    let mut sim = Simulation::new(false, None).unwrap();
+    sim.register_director(Director::new(10, 0).unwrap()).unwrap();
+    sim.step().unwrap();
+
+    let mut late = Director::new(10, 1).unwrap();
+    let neuron = late.add_vectorized_population("neuron", Shape::D1(1), LifParams::new(0.9)).unwrap();
+    late.schedule_spikes(&neuron, &[2]).unwrap();
+    assert!(matches!(sim.register_director(late), Err(Error::AlreadyRunning)));
+    assert_eq!(sim.directors().len(), 1);
+}
+
+#[test]
+fn lowered_threshold_is_compared_on_the_next_input() {
+    for backend in BACKENDS {
+        let mut sim = Simulation::new(false, None).unwrap();
+        let director = sim.register_director(Director::new(10, 0).unwrap()).unwrap();
+        let neuron = population(director, backend, "neuron", 1, LifParams::new(1.)).unwrap();
+        let id = neuron[0];
+        director.schedule_pulse(&neuron, 2..3, 0.6).unwrap();
+        director.schedule_callback(4, move |neurons| neurons.set_threshold(id, 0.5)).unwrap();
+        director.schedule_pulse(&neuron, 6..7, 0.1).unwrap();
+        director.record_spikes(&neuron).unwrap();
+        sim.start().unwrap();
+        assert_eq!(sim.directors()[0].recorded_spikes(), &[(id, 6)], "{backend:?}");
+    }
+}
+
+#[test]
+fn injection_noise_does_not_depend_on_other_injections() {
+    let run = |inject_first_into_other: bool| {
+        let mut sim = Simulation::new(false, None).unwrap();
+        let director = sim.register_director(Director::new(20, 0).unwrap()).unwrap();
+        let neurons = director
+            .add_vectorized_population("neurons", Shape::D1(2), LifParams::new(0.9).with_threshold(f32::MAX))
+            .unwrap();
+        let noise = CurrentSource::WhiteNoise { mean: 0., std_dev: 1. };
+        if inject_first_into_other {
+            director.inject_current(&neurons[0..1], noise.clone()).unwrap();
+        }
+        director.inject_current(&neurons[1..2], noise).unwrap();
+        director.record_potentials(&neurons[1..2]).unwrap();
+        sim.start().unwrap();
+        sim.directors()[0].recorded_potentials()[&neurons[1]].clone()
+    };
+    assert_eq!(run(false), run(true));
+}
+
+#[test]
+fn input_of_one_step_is_summed_before_the_threshold_check() {
+    for backend in BACKENDS {
+        let mut sim = Simulation::new(false, None).unwrap();
+        let director = sim.register_director(Director::new(10, 0).unwrap()).unwrap();
+        let sources = director.add_population::<LifNeuron>("sources", Shape::D1(3), LifParams::new(1.)).unwrap();
+        let targets = population(director, backend, "targets", 2, LifParams::new(1.)).unwrap();
+        /* neither 0.6 alone reaches the threshold of 1; 1.2 would, but not once -0.6 is added */
+        director.create_link(sources[0], targets[0], 0.6).unwrap();
+        director.create_link(sources[1], targets[0], 0.6).unwrap();
+        director.create_link(sources[0], targets[1], -0.6).unwrap();
+        director.create_link(sources[2], targets[1], 1.2).unwrap();
+        for source in sources.iter() {
+            director.schedule_spike(*source, 2).unwrap();
+        }
+        director.record_spikes(&targets).unwrap();
+        director.record_potentials(&targets[1..2]).unwrap();
+        sim.run_for(3).unwrap();
+        let director = &sim.directors()[0];
+        assert_eq!(director.recorded_spikes(), &[(targets[0], 2)], "{backend:?}");
+        let potential = director.recorded_potentials()[&targets[1]][2];
+        assert_eq!(potential.0, 2, "{backend:?}");
+        assert!((potential.1 - 0.6).abs() < 1e-6, "{backend:?}: {potential:?}");
+    }
+}
+
+/// Spikes and sorted weights of a noisy, randomly linked network in director `id`, registered
+/// after `registered_before` other directors.
+fn seeded_run(seed: Option<u64>, id: u32, registered_before: &[u32]) -> (Vec<(NeuronUniqueId, u32)>, Vec<Link>) {
+    let mut sim = Simulation::new(false, None).unwrap();
+    if let Some(seed) = seed {
+        sim.set_seed(seed);
+    }
+    for other in registered_before {
+        sim.register_director(Director::new(30, *other).unwrap()).unwrap();
+    }
+    let director = sim.register_director(Director::new(30, id).unwrap()).unwrap();
+    let input = director.add_vectorized_population("input", Shape::D1(4), LifParams::new(0.8)).unwrap();
+    let output = director.add_vectorized_population("output", Shape::D1(4), LifParams::new(0.8)).unwrap();
+    let weights = VecOrValueFloat::Distribution(WeightDistribution::Uniform { low: 0.2, high: 0.8 });
+    director.create_links_by_rule(&input, &output, weights, BatchLinkingRule::Probability(0.5)).unwrap();
+    director.inject_current(&input, CurrentSource::WhiteNoise { mean: 0.3, std_dev: 0.5 }).unwrap();
+    director.record_spikes(&output).unwrap();
+    sim.start().unwrap();
+
+    let director = sim.directors().last().unwrap();
+    let mut links: Vec<Link> = director.links().collect();
+    links.sort_by_key(|link| (link.source, link.destination));
+    (director.recorded_spikes().to_vec(), links)
+}
+
+#[test]
+fn the_same_seed_reproduces_spikes_and_weights() {
+    let first = seeded_run(Some(11), 2, &[]);
+    assert!(!first.0.is_empty() && !first.1.is_empty());
+    assert_eq!(seeded_run(Some(11), 2, &[]), first);
+    /* director streams follow the director id, not the registration order */
+    assert_eq!(seeded_run(Some(11), 2, &[0, 5]), first);
+    assert_ne!(seeded_run(Some(12), 2, &[]), first);
+    assert_ne!(seeded_run(Some(11), 3, &[]), first);
+    assert_eq!(seeded_run(None, 2, &[]), seeded_run(Some(0), 2, &[]));
+}
+
+#[test]
+fn set_seed_reseeds_registered_directors() {
+    let mut sim = Simulation::new(false, None).unwrap();
+    sim.register_director(Director::new(10, 4).unwrap()).unwrap();
+    sim.register_director(Director::new(10, 1).unwrap()).unwrap();
+    let before: Vec<u64> = sim.directors().iter().map(Director::seed).collect();
+    sim.set_seed(99);
+    let after: Vec<u64> = sim.directors().iter().map(Director::seed).collect();
+    assert!(before.iter().zip(&after).all(|(before, after)| before != after));
+
+    let mut reversed = Simulation::new(false, None).unwrap();
+    reversed.set_seed(99);
+    reversed.register_director(Director::new(10, 1).unwrap()).unwrap();
+    reversed.register_director(Director::new(10, 4).unwrap()).unwrap();
+    let reversed: Vec<u64> = reversed.directors().iter().rev().map(Director::seed).collect();
+    assert_eq!(after, reversed);
+}
+
+#[test]
+fn clamped_potential_holds_for_the_window_and_then_decays() {
+    for backend in BACKENDS {
+        let mut sim = Simulation::new(false, None).unwrap();
+        let director = sim.register_director(Director::new(10, 0).unwrap()).unwrap();
+        let neuron = population(director, backend, "neuron", 1, LifParams::new(0.5).with_threshold(2.)).unwrap();
+        director.schedule_pulse(&neuron, 1..2, 0.6).unwrap();
+        director.clamp_potential(&neuron, 2..5, 0.8).unwrap();
+        director.record_potentials(&neuron).unwrap();
+        sim.run_for(8).unwrap();
+        let potentials: Vec<f32> = sim.directors()[0].recorded_potentials()[&neuron[0]].iter().map(|(_, v)| *v).collect();
+        let expected = [0., 0.6, 0.8, 0.8, 0.8, 0.4, 0.2, 0.1];
+        assert!(potentials.iter().zip(&expected).all(|(found, expected)| (found - expected).abs() < 1e-6), "{backend:?}: {potentials:?}");
+        assert_eq!(potentials.len(), expected.len(), "{backend:?}");
+    }
+}
@@ -0,0 +1,101 @@
+//! Offline surrogate-gradient training of director populations.
+
+use rust_nn_framewrk::neural_sim::error::Error;
+use rust_nn_framewrk::neural_sim::introspection::Link;
+use rust_nn_framewrk::neural_sim::neuron::lif_neuron::LifParams;
+use rust_nn_framewrk::neural_sim::population::{Population, Shape};
+use rust_nn_framewrk::neural_sim::readout::{Classifier, Sample};
+use rust_nn_framewrk::neural_sim::training::{SurrogateNetwork, TrainingConfig};
+use rust_nn_framewrk::neural_sim::{BatchLinkingRule, ControllingUnit, Director, Simulation, VecOrValueFloat, WeightDistribution};
+
+fn layers(director: &mut Director) -> Vec<Population> {
+    let input = director.add_vectorized_population("input", Shape::D1(4), LifParams::new(0.)).unwrap();
+    let hidden = director.add_vectorized_population("hidden", Shape::D1(6), LifParams::new(0.8)).unwrap();
+    let output = director.add_vectorized_population("output", Shape::D1(2), LifParams::new(0.8)).unwrap();
+    let weights = || VecOrValueFloat::Random { distribution: WeightDistribution::Uniform { low: 0., high: 0.6 }, seed: 3 };
+    director.create_links_by_rule(&input, &hidden, weights(), BatchLinkingRule::FullyConnected).unwrap();
+    director.create_links_by_rule(&hidden, &output, weights(), BatchLinkingRule::FullyConnected).unwrap();
+    vec![input, hidden, output]
+}
+
+/// Class 0 drives the first half of the input, class 1 the second half, at jittered steps.
+fn dataset() -> Vec<Sample> {
+    (0..16)
+        .map(|index| {
+            let label = index % 2;
+            let offset = (index / 2) as u32 % 3;
+            let input = (0..4)
+                .map(|neuron| if neuron / 2 == label { vec![offset, offset + 3, offset + 6] } else { Vec::new() })
+                .collect();
+            Sample { input, label }
+        })
+        .collect()
+}
+
+fn sorted_links(director: &Director) -> Vec<Link> {
+    let mut links: Vec<Link> = director.links().collect();
+    links.sort_by_key(|link| (link.source, link.destination));
+    links
+}
+
+#[test]
+fn two_class_task_converges() {
+    let mut sim = Simulation::new(false, None).unwrap();
+    let director = sim.register_director(Director::new(10, 0).unwrap()).unwrap();
+    let populations = layers(director);
+    let mut network = SurrogateNetwork::from_director(director, &populations).unwrap();
+    let dataset = dataset();
+    let config = TrainingConfig { epochs: 40, batch_size: 4, learning_rate: 0.05, steps: 10, ..Default::default() };
+
+    let losses = network.train(&dataset, &config).unwrap();
+    assert!(losses[losses.len() - 1] < losses[0] / 2., "loss did not drop: {losses:?}");
+    assert_eq!(network.accuracy(&dataset, config.steps).unwrap(), 1.);
+
+    let before = sorted_links(director);
+    network.write_back(director).unwrap();
+    let after = sorted_links(director);
+    assert_eq!(after.len(), before.len());
+    assert!(before.iter().zip(&after).all(|(before, after)| (before.source, before.destination) == (after.source, after.destination)));
+    assert!(before.iter().zip(&after).filter(|(before, after)| before.weight != after.weight).count() > after.len() / 2);
+
+    /* without recurrent links the director integrates exactly like the trained copy */
+    let classifier = Classifier::new(0, populations[0].clone(), populations[2].clone(), config.steps);
+    let evaluation = classifier.evaluate(&mut sim, &dataset).unwrap();
+    let expected: Vec<Option<usize>> = dataset.iter().map(|sample| network.predict(&sample.input, config.steps).unwrap()).collect();
+    assert_eq!(evaluation.predictions, expected);
+    assert_eq!(evaluation.accuracy, 1.);
+}
+
+#[test]
+fn recurrent_population_trains() {
+    let mut director = Director::new(10, 0).unwrap();
+    let populations = layers(&mut director);
+    let (hidden, output) = (&populations[1], &populations[2]);
+    let recurrent = || VecOrValueFloat::Random { distribution: WeightDistribution::Uniform { low: -0.2, high: 0.2 }, seed: 5 };
+    director.create_links_by_rule(hidden, hidden, recurrent(), BatchLinkingRule::FullyConnected).unwrap();
+    director.create_links_by_rule(output, hidden, recurrent(), BatchLinkingRule::FullyConnected).unwrap();
+    let mut network = SurrogateNetwork::from_director(&director, &populations).unwrap();
+    let dataset = dataset();
+    let config = TrainingConfig { epochs: 40, batch_size: 4, learning_rate: 0.05, steps: 10, ..Default::default() };
+
+    let losses = network.train(&dataset, &config).unwrap();
+    assert!(losses[losses.len() - 1] < losses[0] / 2., "loss did not drop: {losses:?}");
+    assert_eq!(network.accuracy(&dataset, config.steps).unwrap(), 1.);
+
+    let before = sorted_links(&director);
+    network.write_back(&mut director).unwrap();
+    let recurrent_changed = before
+        .iter()
+        .filter(|link| hidden.contains(link.destination) && !populations[0].contains(link.source))
+        .filter(|link| director.weight(link.source, link.destination) != Some(link.weight))
+        .count();
+    assert!(recurrent_changed > 0);
+}
+
+#[test]
+fn links_into_the_input_are_rejected() {
+    let mut director = Director::new(10, 0).unwrap();
+    let populations = layers(&mut director);
+    director.create_link(populations[1][0], populations[0][0], 0.5).unwrap();
+    assert!(matches!(SurrogateNetwork::from_director(&director, &populations), Err(Error::Training(_))));
+}